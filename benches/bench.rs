@@ -3,7 +3,7 @@ extern crate criterion;
 extern crate fast_sweeping;
 
 use criterion::{Bencher, Criterion};
-use fast_sweeping::level_set;
+use fast_sweeping::{eikonal, level_set};
 use fast_sweeping::*;
 use std::time::Duration;
 
@@ -73,6 +73,31 @@ fn bench_init_2d(b: &mut Bencher, dim: (usize, usize)) {
     });
 }
 
+fn bench_sweep_2d(b: &mut Bencher, dim: (usize, usize)) {
+    let (nx, ny) = dim;
+    let mut u = vec![0.; nx * ny];
+    let mut init = vec![0.; nx * ny];
+
+    let r = 0.3;
+    let hx = 1. / (nx - 1) as f64;
+    let hy = 1. / (ny - 1) as f64;
+
+    for i in 0..nx {
+        for j in 0..ny {
+            let x = i as f64 * hx - 0.5;
+            let y = j as f64 * hy - 0.5;
+            u[i * ny + j] = (x * x + y * y).sqrt() - r;
+        }
+    }
+    level_set::init_dist_2d(&mut init, &u, dim, |p| EuclideanNorm.dual_norm(p));
+
+    let mut d = init.clone();
+    b.iter(|| {
+        d.copy_from_slice(&init);
+        eikonal::fast_sweep_2d(&mut d, dim, |d, v, s| EuclideanNorm.inv_dual_norm(d, v, s));
+    });
+}
+
 fn bench_init_3d(b: &mut Bencher, dim: (usize, usize, usize)) {
     let (nx, ny, nz) = dim;
     let mut u = vec![0.; nx * ny * nz];
@@ -145,6 +170,14 @@ fn bench_init_dist_2d(c: &mut Criterion) {
     );
 }
 
+fn bench_fast_sweep_2d(c: &mut Criterion) {
+    c.bench_function_over_inputs(
+        "fast_sweep_2d",
+        |b, &&size| bench_sweep_2d(b, (size, size)),
+        &[128, 512],
+    );
+}
+
 fn bench_signed_distance_3d(c: &mut Criterion) {
     c.bench_function_over_inputs(
         "signed_distance_3d",
@@ -168,6 +201,7 @@ criterion_group!{
                 .measurement_time(Duration::from_secs(1))
                 .sample_size(5);
     targets = bench_signed_distance_2d, bench_init_dist_2d,
-                bench_signed_distance_3d, bench_init_dist_3d, bench_l1_distance_2d
+                bench_signed_distance_3d, bench_init_dist_3d, bench_l1_distance_2d,
+                bench_fast_sweep_2d
 }
 criterion_main!(benches);
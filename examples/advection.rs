@@ -0,0 +1,91 @@
+//! Moves a curve in the normal direction with a speed given only on the curve, using the
+//! extension of the speed to the whole grid computed together with the signed distance.
+extern crate docopt;
+extern crate fast_sweeping;
+extern crate gnuplot;
+extern crate isosurface;
+extern crate rustc_serialize;
+
+use fast_sweeping::extension::signed_distance_2d_extend;
+use gnuplot::{AutoOption, AxesCommon, Figure, PlotOption};
+
+const USAGE: &'static str = "
+Level set advection with an extended normal velocity.
+
+Usage:
+  advection [options]
+  advection (-h | --help)
+  advection --version
+
+Options:
+  -n INT                Mesh  resolution (n^2). [default: 64]
+  --steps INT           Number of time steps. [default: 40]
+  --svg FILE            Produce svg output to FILE.
+  -h, --help            Show this screen.
+  --version             Show version.
+";
+
+#[derive(Debug, RustcDecodable)]
+pub struct Args {
+    flag_n: usize,
+    flag_steps: usize,
+    flag_svg: Option<String>,
+}
+
+fn main() {
+    let args: Args = docopt::Docopt::new(USAGE)
+        .and_then(|d| d.decode())
+        .unwrap_or_else(|e| e.exit());
+
+    let n = args.flag_n;
+    let dim = (n + 1, n + 1);
+    let h = 1. / n as f64;
+    let coords = |s: usize| ((s / dim.1) as f64 * h - 0.5, (s % dim.1) as f64 * h - 0.5);
+
+    let mut u: Vec<f64> = (0..dim.0 * dim.1)
+        .map(|s| {
+            let (x, y) = coords(s);
+            x.hypot(y) - 0.2
+        })
+        .collect();
+    let init_verts = isosurface::marching_triangles(&u, dim, 0.);
+
+    // the normal speed is known only on the curve: faster on the right
+    let speed_on_curve = |x: f64, y: f64| 0.5 + 0.4 * y.atan2(x).cos();
+    let dt = 0.5 * h;
+    let mut d = vec![0.; dim.0 * dim.1];
+    let mut q = vec![0.; dim.0 * dim.1];
+    for step in 0..args.flag_steps {
+        for (s, q) in q.iter_mut().enumerate() {
+            let (x, y) = coords(s);
+            *q = speed_on_curve(x, y);
+        }
+        signed_distance_2d_extend(&mut d, &mut q, &u, dim, h);
+        // `d` is a distance function, so `u_t + q |∇u| = 0` becomes `u_t + q = 0`
+        for s in 0..u.len() {
+            u[s] = d[s] - dt * q[s];
+        }
+        let area = u.iter().filter(|&&u| u < 0.).count() as f64 * h * h;
+        println!("step {}: area {:.4}", step + 1, area);
+    }
+    let verts = isosurface::marching_triangles(&u, dim, 0.);
+
+    let mut fg = Figure::new();
+    if let Some(f) = args.flag_svg {
+        fg.set_terminal("svg size 1280, 1280", &f);
+    }
+    {
+        let axes = fg.axes2d();
+        axes.set_aspect_ratio(AutoOption::Fix(1.));
+        for (verts, c) in [init_verts, verts].iter().zip(&["blue", "red"]) {
+            for line in verts.components() {
+                axes.lines(
+                    line.iter().map(|p| p[0]),
+                    line.iter().map(|p| p[1]),
+                    &[PlotOption::Color(c)],
+                );
+            }
+        }
+    }
+    fg.show();
+}
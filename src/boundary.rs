@@ -0,0 +1,297 @@
+//! Boundary conditions on the edges of the computational domain.
+//!
+//! By default, the grid is simply cut off at its boundary and no information enters from the
+//! outside. The functions in this module allow for a different `BoundaryCondition` on each of the
+//! four edges of a 2D grid.
+//!
+//! The conditions are given as an array `[i = 0, i = ni - 1, j = 0, j = nj - 1]`, where `(ni, nj)`
+//! are the dimensions of the grid and `j` is the fast (row-major) index.
+use super::min;
+use eikonal;
+use level_set::{init_dist_2d, init_square_2d};
+
+/// Condition imposed on one edge of the grid.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BoundaryCondition {
+    /// Nothing enters through the edge. This is the behavior of `signed_distance_2d`.
+    Open,
+    /// The grid wraps around: the opposite edge is the neighbor of this edge at the distance of
+    /// one grid step. The opposite edge must be `Periodic` as well.
+    Periodic,
+    /// The level set is mirrored across the edge (homogeneous Neumann condition).
+    ///
+    /// For the distance function this is equivalent to `Open`, since the mirror image of the level
+    /// set is never closer to a node than the level set itself.
+    Reflect,
+    /// The (unsigned) distance of the nodes on the edge is at most the given value. For instance,
+    /// `Dirichlet(0.)` makes the edge a part of the interface.
+    Dirichlet(f64),
+    /// The interface continues beyond the edge: the level set function is extrapolated linearly
+    /// across the edge, with the slope between the two outermost nodes, to as many layers of ghost
+    /// nodes as there are nodes along the axis. The distance is computed on the extended grid.
+    ///
+    /// This is useful for a semi-infinite domain where the interface runs off the sampled region.
+    /// It is handled by [`signed_distance_2d_bc`](../fn.signed_distance_2d_bc.html); the other
+    /// functions of this module treat it as `Open`.
+    Extrapolate,
+}
+
+use self::BoundaryCondition::*;
+
+pub(crate) fn assert_periodic_pairs(bc: [BoundaryCondition; 4]) {
+    assert!(
+        (bc[0] == Periodic) == (bc[1] == Periodic) && (bc[2] == Periodic) == (bc[3] == Periodic),
+        "Periodic boundary conditions must be imposed on opposite edges, got {:?}",
+        bc
+    );
+}
+
+/// Value at the index `i`, possibly outside of `0..n`, of the linear extrapolation of the values
+/// `f(0)`, ..., `f(n - 1)` from the two values nearest to `i`.
+#[inline(always)]
+fn extrapolate<F>(f: F, n: usize, i: isize) -> f64
+where
+    F: Fn(usize) -> f64,
+{
+    let last = n as isize - 1;
+    if i < 0 && n > 1 {
+        f(0) + i as f64 * (f(1) - f(0))
+    } else if i > last && n > 1 {
+        f(n - 1) + (i - last) as f64 * (f(n - 1) - f(n - 2))
+    } else {
+        f(i.max(0).min(last) as usize)
+    }
+}
+
+/// Extends the level set function `u` beyond the edges with the `Extrapolate` condition.
+///
+/// Returns the extended values, the dimensions of the extended grid and the position `(i, j)` of
+/// the node `(0, 0)` of the original grid in it.
+pub(crate) fn extrapolate_2d(
+    u: &[f64],
+    dim: (usize, usize),
+    bc: [BoundaryCondition; 4],
+) -> (Vec<f64>, (usize, usize), (usize, usize)) {
+    let (ni, nj) = dim;
+    let pad = |c: BoundaryCondition, n: usize| if c == Extrapolate { n - 1 } else { 0 };
+    let (oi, oj) = (pad(bc[0], ni), pad(bc[2], nj));
+    let (mi, mj) = (ni + oi + pad(bc[1], ni), nj + oj + pad(bc[3], nj));
+
+    let mut v = Vec::with_capacity(mi * mj);
+    for a in 0..mi {
+        let i = a as isize - oi as isize;
+        for b in 0..mj {
+            let j = b as isize - oj as isize;
+            v.push(extrapolate(
+                |i| extrapolate(|j| u[i * nj + j], nj, j),
+                ni,
+                i,
+            ));
+        }
+    }
+    (v, (mi, mj), (oi, oj))
+}
+
+/// Index of the neighbor of node `i` in the direction `-s` on an axis with `n` nodes, taking into
+/// account the boundary conditions `low` at `i = 0` and `high` at `i = n - 1`.
+///
+/// Returns `None` if there is no neighbor.
+#[inline(always)]
+fn upwind(
+    i: usize,
+    s: isize,
+    n: usize,
+    low: BoundaryCondition,
+    high: BoundaryCondition,
+) -> Option<usize> {
+    let (edge, bc) = if s == 1 { (0, low) } else { (n - 1, high) };
+    if i != edge {
+        return Some((i as isize - s) as usize);
+    }
+    match bc {
+        Periodic => Some(n - 1 - edge),
+        Reflect if n > 1 => Some((i as isize + s) as usize),
+        _ => None,
+    }
+}
+
+/// Initializes the distance function near the free boundary, like
+/// [`init_dist_2d`](../level_set/fn.init_dist_2d.html), with the boundary conditions `bc`.
+///
+/// With `Periodic` conditions, the squares connecting the opposite edges of the grid are also
+/// taken into account. The nodes on the edges with a `Dirichlet(g)` condition are set to at most
+/// `g`; note that `g` is in the units of the grid step, like the rest of `d`.
+pub fn init_dist_2d_bc<F>(
+    d: &mut [f64],
+    u: &[f64],
+    dim: (usize, usize),
+    bc: [BoundaryCondition; 4],
+    mut dual_norm: F,
+) where
+    F: FnMut([f64; 2]) -> f64,
+{
+    let (ni, nj) = dim;
+    assert_periodic_pairs(bc);
+    init_dist_2d(d, u, dim, &mut dual_norm);
+
+    if bc[0] == Periodic {
+        let (a, b) = ((ni - 1) * nj, 0);
+        for j in 1..nj {
+            let v = [a + j - 1, a + j, b + j - 1, b + j];
+            init_square_2d(d, v, [u[v[0]], u[v[1]], u[v[2]], u[v[3]]], &mut dual_norm);
+        }
+    }
+    if bc[2] == Periodic {
+        let (a, b) = (nj - 1, 0);
+        for i in 1..ni {
+            let (s0, s1) = ((i - 1) * nj, i * nj);
+            let v = [s0 + a, s0 + b, s1 + a, s1 + b];
+            init_square_2d(d, v, [u[v[0]], u[v[1]], u[v[2]], u[v[3]]], &mut dual_norm);
+        }
+    }
+    if bc[0] == Periodic && bc[2] == Periodic {
+        let s = (ni - 1) * nj;
+        let v = [s + nj - 1, s, nj - 1, 0];
+        init_square_2d(d, v, [u[v[0]], u[v[1]], u[v[2]], u[v[3]]], &mut dual_norm);
+    }
+
+    for (e, &c) in bc.iter().enumerate() {
+        if let Dirichlet(g) = c {
+            let (start, step, len) = match e {
+                0 => (0, 1, nj),
+                1 => ((ni - 1) * nj, 1, nj),
+                2 => (0, nj, ni),
+                _ => (nj - 1, nj, ni),
+            };
+            for p in 0..len {
+                let s = start + p * step;
+                d[s] = min(d[s], g);
+            }
+        }
+    }
+}
+
+/// Computes the solution of the eikonal equation in 2D like
+/// [`fast_sweep_2d`](../eikonal/fn.fast_sweep_2d.html), with the boundary conditions `bc`.
+///
+/// Since the information can wrap around the grid or reflect from the edges, the sweeps in the
+/// four directions are repeated until `d` does not change anymore. `Dirichlet` conditions are
+/// handled by the initialization and act as `Open` conditions here.
+pub fn fast_sweep_2d_bc<F>(
+    d: &mut [f64],
+    dim: (usize, usize),
+    bc: [BoundaryCondition; 4],
+    inv_norm: F,
+) where
+    F: Fn(f64, [f64; 2], [f64; 2]) -> f64,
+{
+    let (ni, nj) = dim;
+    assert_eq!(ni * nj, d.len());
+    assert_periodic_pairs(bc);
+
+    eikonal::sweep_2d_repeat(
+        d,
+        dim,
+        0.,
+        |_| false,
+        |d, s, (i, j), (si, sj)| {
+            let vi = upwind(i, si, ni, bc[0], bc[1]).map_or(std::f64::MAX, |i| d[i * nj + j]);
+            let vj = upwind(j, sj, nj, bc[2], bc[3]).map_or(std::f64::MAX, |j| d[i * nj + j]);
+            inv_norm(d[s], [vi, vj], [si as f64, sj as f64])
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use norm::{DualNorm, L1Norm};
+
+    #[test]
+    fn mixed_conditions() {
+        let (ni, nj) = (8, 10);
+        let bc = [Periodic, Periodic, Reflect, Dirichlet(0.)];
+        let mut d = vec![0.; ni * nj];
+        // no interface: only the Dirichlet edge is seeded
+        let u = vec![1.; ni * nj];
+        init_dist_2d_bc(&mut d, &u, (ni, nj), bc, |p| L1Norm.dual_norm(p));
+        // seeds near the periodic edge i = 0 and near the reflecting edge j = 0
+        let seeds = [(0, 4), (5, 1)];
+        for &(i, j) in &seeds {
+            d[i * nj + j] = 0.;
+        }
+        fast_sweep_2d_bc(&mut d, (ni, nj), bc, |d, v, s| {
+            L1Norm.inv_dual_norm(d, v, s)
+        });
+
+        for i in 0..ni {
+            for j in 0..nj {
+                // Manhattan distance to the seeds (wrapped in i), their mirror images across the
+                // edge j = 0, and to the Dirichlet edge
+                let mut e = (nj - 1 - j) as f64;
+                for &(si, sj) in &seeds {
+                    let di = if i > si { i - si } else { si - i };
+                    let di = di.min(ni - di) as f64;
+                    for &sj in &[sj as isize, -(sj as isize)] {
+                        e = e.min(di + (j as isize - sj).abs() as f64);
+                    }
+                }
+                assert_eq!(d[i * nj + j], e, "node ({}, {})", i, j);
+            }
+        }
+        // the information wrapped around the periodic edge
+        assert_eq!(d[(ni - 1) * nj + 4], 1.);
+    }
+
+    #[test]
+    fn extrapolated_edge() {
+        // a circle running off the edge j = 0
+        let n = 33;
+        let h = 1. / (n - 1) as f64;
+        let (ci, cj, r) = (0.5, -0.2, 0.35);
+        let mut u = vec![0.; n * n];
+        for i in 0..n {
+            for j in 0..n {
+                let (x, y) = (i as f64 * h - ci, j as f64 * h - cj);
+                u[i * n + j] = x.hypot(y) - r;
+            }
+        }
+        let mut open = vec![0.; n * n];
+        let mut ext = vec![0.; n * n];
+        ::signed_distance_2d_bc(&mut open, &u, (n, n), h, [Open; 4]);
+        ::signed_distance_2d_bc(&mut ext, &u, (n, n), h, [Open, Open, Extrapolate, Open]);
+
+        let (mut err_open, mut err_ext) = (0f64, 0f64);
+        for i in 0..n {
+            for j in 0..n {
+                let s = i * n + j;
+                let e = (i as f64 * h - ci).hypot(j as f64 * h - cj) - r;
+                err_open = err_open.max((open[s] - e).abs());
+                err_ext = err_ext.max((ext[s] - e).abs());
+            }
+        }
+        // the parts of the circle outside the grid are missed by the open condition
+        assert!(err_ext < 0.35 * h, "{}", err_ext);
+        assert!(err_open > 0.5 * h, "{}", err_open);
+    }
+
+    #[test]
+    fn open_conditions_match_default() {
+        let n = 17;
+        let h = 1. / (n - 1) as f64;
+        let mut u = vec![0.; n * n];
+        for i in 0..n {
+            for j in 0..n {
+                let (x, y) = (i as f64 * h - 0.4, j as f64 * h - 0.55);
+                u[i * n + j] = x.hypot(y) - 0.3;
+            }
+        }
+        let mut d = vec![0.; n * n];
+        let mut d_bc = vec![0.; n * n];
+        ::signed_distance_2d(&mut d, &u, (n, n), h);
+        ::signed_distance_2d_bc(&mut d_bc, &u, (n, n), h, [Open; 4]);
+        for (a, b) in d.iter().zip(&d_bc) {
+            assert!((a - b).abs() < 1e-12, "{} != {}", a, b);
+        }
+    }
+}
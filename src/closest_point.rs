@@ -0,0 +1,238 @@
+//! Closest point transform: the nearest point of the interface for every node of the grid.
+//!
+//! The closest points are seeded in the nodes of the triangles through which the zero level set
+//! passes (see [`signed_distance_2d`](../fn.signed_distance_2d.html)) by projecting the node onto
+//! the zero line of the linear interpolation, and then propagated by sweeping: every node takes
+//! the closest of the points found by its neighbors.
+//!
+//! The coordinates are physical, the node `(i, j)` being at `(i h, j h)`.
+use boundary::{assert_periodic_pairs, BoundaryCondition};
+use eikonal;
+
+/// Computes the closest point on the _zero_ level set of `u` for every node of a regular 2D grid.
+///
+/// Nodes that cannot be reached, for instance when `u` does not change sign, get `[NaN, NaN]`.
+pub fn closest_point_2d(cp: &mut [[f64; 2]], u: &[f64], dim: (usize, usize), h: f64) {
+    closest_point_2d_bc(cp, u, dim, h, [BoundaryCondition::Open; 4]);
+}
+
+/// Computes the closest points like [`closest_point_2d`](fn.closest_point_2d.html) together with
+/// the signed distance `d` of every node from its closest point, negative where `u < 0`.
+///
+/// Unlike [`signed_distance_2d`](../fn.signed_distance_2d.html), the distance is measured to the
+/// propagated closest point and not computed by the upwind scheme. The nodes that cannot be
+/// reached get `±std::f64::MAX`.
+pub fn closest_point_2d_signed(
+    cp: &mut [[f64; 2]],
+    d: &mut [f64],
+    u: &[f64],
+    dim: (usize, usize),
+    h: f64,
+) {
+    let nj = dim.1;
+    assert_eq!(dim.0 * nj, d.len());
+    closest_point_2d(cp, u, dim, h);
+    for (s, (d, p)) in d.iter_mut().zip(cp.iter()).enumerate() {
+        let (x, y) = ((s / nj) as f64 * h, (s % nj) as f64 * h);
+        let e = if p[0].is_nan() {
+            std::f64::MAX
+        } else {
+            (p[0] - x).hypot(p[1] - y)
+        };
+        *d = if u[s] < 0. { -e } else { e };
+    }
+}
+
+/// Computes the closest points like [`closest_point_2d`](fn.closest_point_2d.html), with the
+/// boundary conditions `bc` (see the [`boundary` module](../boundary/index.html)).
+///
+/// With `Periodic` conditions the nearest point of the interface may lie across the seam. The
+/// returned point is then the periodic image that is nearest to the node, so it can lie outside of
+/// the domain `[0, ni h) × [0, nj h)`, by less than one period. That is, `cp - x` is always the
+/// displacement from the node `x` to its closest point, and the canonical representative in the
+/// domain is obtained by reducing the coordinates modulo `ni h` and `nj h`, respectively.
+///
+/// The other conditions act as `Open`.
+pub fn closest_point_2d_bc(
+    cp: &mut [[f64; 2]],
+    u: &[f64],
+    dim: (usize, usize),
+    h: f64,
+    bc: [BoundaryCondition; 4],
+) {
+    let (ni, nj) = dim;
+    assert_eq!(ni * nj, u.len());
+    assert_eq!(ni * nj, cp.len());
+    assert_periodic_pairs(bc);
+    let periodic = [
+        bc[0] == BoundaryCondition::Periodic,
+        bc[2] == BoundaryCondition::Periodic,
+    ];
+
+    // displacement to the closest point in the units of the grid step
+    let mut r = vec![[std::f64::NAN; 2]; ni * nj];
+    let mut dist = vec![std::f64::MAX; ni * nj];
+
+    // squares with the lower corner (i, j), including the ones across the periodic seams
+    let si = if periodic[0] {
+        ni
+    } else {
+        ni.saturating_sub(1)
+    };
+    let sj = if periodic[1] {
+        nj
+    } else {
+        nj.saturating_sub(1)
+    };
+    for i in 0..si {
+        for j in 0..sj {
+            let (i1, j1) = ((i + 1) % ni, (j + 1) % nj);
+            let v = [i * nj + j, i * nj + j1, i1 * nj + j, i1 * nj + j1];
+            for t in &[[0, 1, 3], [0, 2, 3]] {
+                let w = [u[v[t[0]]], u[v[t[1]]], u[v[t[2]]]];
+                if w.iter().all(|&w| w > 0.) || w.iter().all(|&w| w < 0.) {
+                    continue;
+                }
+                // gradient of the linear interpolation on the triangle
+                let g = if t[1] == 1 {
+                    [w[2] - w[1], w[1] - w[0]]
+                } else {
+                    [w[1] - w[0], w[2] - w[1]]
+                };
+                let g2 = g[0] * g[0] + g[1] * g[1];
+                for k in 0..3 {
+                    let s = v[t[k]];
+                    let disp = if g2 > 0. {
+                        [-w[k] / g2 * g[0], -w[k] / g2 * g[1]]
+                    } else {
+                        [0., 0.]
+                    };
+                    let e = disp[0].hypot(disp[1]);
+                    if e < dist[s] {
+                        dist[s] = e;
+                        r[s] = disp;
+                    }
+                }
+            }
+        }
+    }
+
+    let neighbor = |i: usize, s: isize, n: usize, periodic: bool| {
+        let edge = if s == 1 { 0 } else { n - 1 };
+        if i != edge {
+            Some((i as isize - s) as usize)
+        } else if periodic && n > 1 {
+            Some(n - 1 - edge)
+        } else {
+            None
+        }
+    };
+    eikonal::sweep_2d_repeat(
+        &mut dist,
+        dim,
+        0.,
+        |_| false,
+        |dist, s, (i, j), (di, dj)| {
+            let up_i = neighbor(i, di, ni, periodic[0]).map(|a| (a * nj + j, [-di, 0]));
+            let up_j = neighbor(j, dj, nj, periodic[1]).map(|b| (i * nj + b, [0, -dj]));
+            let mut best = dist[s];
+            for &(t, step) in up_i.iter().chain(up_j.iter()) {
+                if dist[t] == std::f64::MAX {
+                    continue;
+                }
+                // the neighbor is one step away, even across a seam
+                let c = [r[t][0] + step[0] as f64, r[t][1] + step[1] as f64];
+                let e = c[0].hypot(c[1]);
+                if e < best {
+                    best = e;
+                    r[s] = c;
+                }
+            }
+            best
+        },
+    );
+
+    for i in 0..ni {
+        for j in 0..nj {
+            let s = i * nj + j;
+            cp[s] = [(i as f64 + r[s][0]) * h, (j as f64 + r[s][1]) * h];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use BoundaryCondition::*;
+
+    #[test]
+    fn periodic_closest_point_wraps() {
+        // zeros at x = 0.05 and x = 0.55, periodic with the period 1 in x
+        let (ni, nj) = (25, 10);
+        let h = 0.04;
+        let mut u = vec![0.; ni * nj];
+        for i in 0..ni {
+            for j in 0..nj {
+                u[i * nj + j] = (2. * std::f64::consts::PI * (i as f64 * h - 0.05)).sin();
+            }
+        }
+        let mut cp = vec![[0.; 2]; ni * nj];
+        closest_point_2d_bc(&mut cp, &u, (ni, nj), h, [Periodic, Periodic, Open, Open]);
+        for i in 0..ni {
+            let x = i as f64 * h;
+            // the nearest zero, possibly across the seam
+            let mut zero = -0.95;
+            for &z in &[0.05, 0.55, 1.05] {
+                if (z - x).abs() < (zero - x).abs() {
+                    zero = z;
+                }
+            }
+            for j in 0..nj {
+                let p = cp[i * nj + j];
+                assert!((p[0] - zero).abs() < 1e-3, "{}: {:?} != {}", i, p, zero);
+                assert!((p[1] - j as f64 * h).abs() < 1e-12);
+            }
+        }
+        // the far side of the seam gets the wrapped representative
+        assert!(cp[23 * nj][0] > 1.);
+
+        // without wrapping the closest point is inside the domain
+        closest_point_2d(&mut cp, &u, (ni, nj), h);
+        assert!((cp[23 * nj][0] - 0.55).abs() < 1e-3);
+        assert!(cp.iter().all(|p| p[0] >= 0. && p[0] < 1.));
+    }
+
+    #[test]
+    fn signed_distance_to_closest_point() {
+        let n = 33;
+        let h = 1. / (n - 1) as f64;
+        let mut u = vec![0.; n * n];
+        for i in 0..n {
+            for j in 0..n {
+                u[i * n + j] = (i as f64 * h - 0.5).hypot(j as f64 * h - 0.45) - 0.3;
+            }
+        }
+        let mut cp = vec![[0.; 2]; n * n];
+        let mut d = vec![0.; n * n];
+        closest_point_2d_signed(&mut cp, &mut d, &u, (n, n), h);
+        for s in 0..n * n {
+            assert_eq!(d[s] < 0., u[s] < 0.);
+            // a propagated point is the closest one of the neighbors, off by a fraction of h
+            assert!((d[s] - u[s]).abs() < 0.5 * h, "{} != {}", d[s], u[s]);
+            // the closest point is on the circle
+            let r = (cp[s][0] - 0.5).hypot(cp[s][1] - 0.45);
+            assert!((r - 0.3).abs() < 1e-2);
+        }
+    }
+
+    #[test]
+    fn no_interface_gives_nan() {
+        let u = vec![1.; 12];
+        let mut cp = vec![[0.; 2]; 12];
+        closest_point_2d(&mut cp, &u, (3, 4), 0.5);
+        assert!(cp.iter().all(|p| p[0].is_nan() && p[1].is_nan()));
+        let mut d = vec![0.; 12];
+        closest_point_2d_signed(&mut cp, &mut d, &u, (3, 4), 0.5);
+        assert!(d.iter().all(|&d| d == std::f64::MAX));
+    }
+}
@@ -0,0 +1,242 @@
+//! Extraction and simplification of the zero contour of a level set function.
+//!
+//! The contour is reconstructed on the same triangles as in
+//! [`signed_distance_2d`](../fn.signed_distance_2d.html): every square of the grid is split along
+//! its diagonal `(0, 0)`–`(1, 1)` and the level set function is linear on each triangle, so the
+//! contour crosses every triangle at most once and there are no ambiguous cases. A node is inside
+//! if `u < 0`, like everywhere else in the crate.
+//!
+//! The coordinates are physical, the node `(i, j)` being at `(i h, j h)`.
+use std::collections::HashMap;
+
+/// An edge of the triangulation given by the indices of its endpoints, the smaller one first.
+type Edge = (usize, usize);
+
+/// Calls `emit` with the two crossed edges of every triangle through which the zero level set
+/// passes, together with the crossing points.
+fn triangle_segments<F>(u: &[f64], dim: (usize, usize), h: f64, mut emit: F)
+where
+    F: FnMut([Edge; 2], [[f64; 2]; 2]),
+{
+    let (ni, nj) = dim;
+    assert_eq!(ni * nj, u.len());
+
+    let point = |s: usize| [(s / nj) as f64 * h, (s % nj) as f64 * h];
+    for i in 1..ni {
+        for j in 1..nj {
+            let s = i * nj + j;
+            for t in &[[s - nj - 1, s - nj, s], [s - nj - 1, s - 1, s]] {
+                let mut edges = [(0, 0); 2];
+                let mut points = [[0.; 2]; 2];
+                let mut n = 0;
+                for k in 0..3 {
+                    let (a, b) = (t[k], t[(k + 1) % 3]);
+                    if (u[a] < 0.) != (u[b] < 0.) {
+                        let r = u[a] / (u[a] - u[b]);
+                        let (pa, pb) = (point(a), point(b));
+                        edges[n] = (a.min(b), a.max(b));
+                        points[n] = [pa[0] + r * (pb[0] - pa[0]), pa[1] + r * (pb[1] - pa[1])];
+                        n += 1;
+                    }
+                }
+                // a triangle with a sign change has exactly two crossed edges
+                if n == 2 {
+                    emit(edges, points);
+                }
+            }
+        }
+    }
+}
+
+/// Extracts the zero contour of `u` on a regular 2D grid with spacing `h` as a list of polylines.
+///
+/// The pieces of the contour in the triangles are chained into ordered paths. A closed contour is
+/// returned with its first point repeated at the end; the open contours end on the boundary of
+/// the grid.
+pub fn contour_polylines(u: &[f64], dim: (usize, usize), h: f64) -> Vec<Vec<[f64; 2]>> {
+    let mut segments = vec![];
+    let mut points: HashMap<Edge, [f64; 2]> = HashMap::new();
+    let mut incident: HashMap<Edge, Vec<usize>> = HashMap::new();
+    triangle_segments(u, dim, h, |e, p| {
+        for k in 0..2 {
+            points.insert(e[k], p[k]);
+            incident.entry(e[k]).or_default().push(segments.len());
+        }
+        segments.push(e);
+    });
+
+    let mut used = vec![false; segments.len()];
+    let mut lines = vec![];
+    // follows the chain of segments starting with the edge `start`
+    let trace = |start: Edge, used: &mut [bool]| {
+        let mut line = vec![points[&start]];
+        let mut edge = start;
+        while let Some(&k) = incident[&edge].iter().find(|&&k| !used[k]) {
+            used[k] = true;
+            edge = if segments[k][0] == edge {
+                segments[k][1]
+            } else {
+                segments[k][0]
+            };
+            line.push(points[&edge]);
+        }
+        line
+    };
+
+    // open polylines start at the edges with a single segment, on the boundary of the grid
+    let mut ends: Vec<Edge> = incident
+        .iter()
+        .filter(|&(_, v)| v.len() == 1)
+        .map(|(&e, _)| e)
+        .collect();
+    ends.sort();
+    for e in ends {
+        if !used[incident[&e][0]] {
+            lines.push(trace(e, &mut used));
+        }
+    }
+    for k in 0..segments.len() {
+        if !used[k] {
+            lines.push(trace(segments[k][0], &mut used));
+        }
+    }
+    lines
+}
+
+/// The length of the zero contour of `u` on a regular 2D grid with spacing `h`, the sum of the
+/// lengths of its pieces in the triangles.
+///
+/// This is the length of the polylines of [`contour_polylines`](fn.contour_polylines.html),
+/// without building them.
+pub fn contour_length(u: &[f64], dim: (usize, usize), h: f64) -> f64 {
+    let mut len = 0.;
+    triangle_segments(u, dim, h, |_, p| {
+        len += (p[1][0] - p[0][0]).hypot(p[1][1] - p[0][1]);
+    });
+    len
+}
+
+/// Distance of the point `p` from the segment `a`–`b`.
+pub(crate) fn segment_dist(p: [f64; 2], a: [f64; 2], b: [f64; 2]) -> f64 {
+    let e = [b[0] - a[0], b[1] - a[1]];
+    let len2 = e[0] * e[0] + e[1] * e[1];
+    let t = if len2 > 0. {
+        (((p[0] - a[0]) * e[0] + (p[1] - a[1]) * e[1]) / len2)
+            .max(0.)
+            .min(1.)
+    } else {
+        0.
+    };
+    (p[0] - a[0] - t * e[0]).hypot(p[1] - a[1] - t * e[1])
+}
+
+/// Simplifies the polyline `line` by the Douglas-Peucker algorithm with the tolerance `tol`.
+///
+/// The result is a subsequence of the vertices of `line` which keeps the first and the last one
+/// (so a closed polyline stays closed), and every vertex of `line` is within `tol` of it.
+pub fn simplify(line: &[[f64; 2]], tol: f64) -> Vec<[f64; 2]> {
+    if line.len() < 3 {
+        return line.to_vec();
+    }
+    let mut keep = vec![false; line.len()];
+    keep[0] = true;
+    keep[line.len() - 1] = true;
+    let mut stack = vec![(0, line.len() - 1)];
+    while let Some((a, b)) = stack.pop() {
+        let mut far = (0., a);
+        for k in a + 1..b {
+            let r = segment_dist(line[k], line[a], line[b]);
+            if r > far.0 {
+                far = (r, k);
+            }
+        }
+        if far.0 > tol {
+            keep[far.1] = true;
+            stack.push((a, far.1));
+            stack.push((far.1, b));
+        }
+    }
+    line.iter()
+        .zip(&keep)
+        .filter(|&(_, &k)| k)
+        .map(|(&p, _)| p)
+        .collect()
+}
+
+/// Extracts the zero contour of `u` like [`contour_polylines`](fn.contour_polylines.html) and
+/// simplifies every polyline with the tolerance `tol`, see [`simplify`](fn.simplify.html).
+pub fn simplified_contour(u: &[f64], dim: (usize, usize), h: f64, tol: f64) -> Vec<Vec<[f64; 2]>> {
+    contour_polylines(u, dim, h)
+        .iter()
+        .map(|line| simplify(line, tol))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn simplified_circle() {
+        let n = 65;
+        let h = 1. / (n - 1) as f64;
+        let (c, r) = ([0.45, 0.5], 0.3);
+        let mut u = vec![0.; n * n];
+        for i in 0..n {
+            for j in 0..n {
+                u[i * n + j] = (i as f64 * h - c[0]).hypot(j as f64 * h - c[1]) - r;
+            }
+        }
+        let lines = contour_polylines(&u, (n, n), h);
+        assert_eq!(lines.len(), 1);
+        let line = &lines[0];
+        assert_eq!(line[0], line[line.len() - 1]);
+        for p in line {
+            assert!(((p[0] - c[0]).hypot(p[1] - c[1]) - r).abs() < 1e-3);
+        }
+        let len: f64 = line.windows(2).map(|w| segment_dist(w[0], w[1], w[1])).sum();
+        assert!((contour_length(&u, (n, n), h) - len).abs() < 1e-12);
+        assert!((len - 2. * std::f64::consts::PI * r).abs() < 1e-2, "{}", len);
+
+        let tol = 2e-3;
+        let simple = simplified_contour(&u, (n, n), h, tol);
+        let simple = &simple[0];
+        assert_eq!(simple[0], simple[simple.len() - 1]);
+        assert!(
+            simple.len() * 4 < line.len(),
+            "{} {}",
+            simple.len(),
+            line.len()
+        );
+        for p in line {
+            let r = simple
+                .windows(2)
+                .map(|w| segment_dist(*p, w[0], w[1]))
+                .fold(std::f64::MAX, f64::min);
+            assert!(r <= tol + 1e-12);
+        }
+        // the chords of the polygon stay close to the circle
+        for w in simple.windows(2) {
+            let m = [0.5 * (w[0][0] + w[1][0]), 0.5 * (w[0][1] + w[1][1])];
+            assert!(r - (m[0] - c[0]).hypot(m[1] - c[1]) < tol + 1e-3);
+        }
+    }
+
+    #[test]
+    fn open_contour_ends_on_boundary() {
+        let n = 9;
+        let h = 0.125;
+        let u: Vec<f64> = (0..n * n)
+            .map(|s| (s / n) as f64 * h + 0.5 * (s % n) as f64 * h - 0.6)
+            .collect();
+        let lines = contour_polylines(&u, (n, n), h);
+        assert_eq!(lines.len(), 1);
+        // a straight line is simplified to its endpoints
+        let simple = simplify(&lines[0], 1e-9);
+        assert_eq!(simple.len(), 2);
+        for p in &simple {
+            assert!(p[0] == 0. || p[1] == 0. || p[0] == 1. || p[1] == 1.);
+            assert!((p[0] + 0.5 * p[1] - 0.6).abs() < 1e-12);
+        }
+    }
+}
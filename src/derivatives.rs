@@ -0,0 +1,309 @@
+//! Finite difference derivatives of distance functions.
+//!
+//! The first axis of the grid is called `x` and the second `y`, that is, the node `(i, j)` is at
+//! the position `(i h, j h)`.
+
+/// Finite difference stencil for the computation of the gradient.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GradientStencil {
+    /// Central differences in the interior and one-sided differences on the boundary.
+    Central,
+    /// Central differences averaged over the three neighboring rows (columns) with the weights
+    /// `1/4, 1/2, 1/4` (the Sobel operator). This gives smoother normals on a rough field.
+    Sobel,
+}
+
+impl Default for GradientStencil {
+    fn default() -> Self {
+        GradientStencil::Central
+    }
+}
+
+/// Difference `b - a` that is zero for equal values, including infinities.
+#[inline(always)]
+fn sub(b: f64, a: f64) -> f64 {
+    if a == b {
+        0.
+    } else {
+        b - a
+    }
+}
+
+/// Finite difference of `f` at node `k` of an axis with `n` nodes.
+#[inline(always)]
+fn diff<F>(n: usize, k: usize, h: f64, f: F) -> f64
+where
+    F: Fn(usize) -> f64,
+{
+    let (a, b) = if k == 0 {
+        (0, 1)
+    } else if k == n - 1 {
+        (n - 2, n - 1)
+    } else {
+        (k - 1, k + 1)
+    };
+    sub(f(b), f(a)) / ((b - a) as f64 * h)
+}
+
+/// Second difference of `f` at node `k` of an axis with `n` nodes; at the boundary the stencil of
+/// the neighboring node is used.
+#[inline(always)]
+fn second_diff<F>(n: usize, k: usize, h: f64, f: F) -> f64
+where
+    F: Fn(usize) -> f64,
+{
+    let c = if k == 0 {
+        1
+    } else if k == n - 1 {
+        n - 2
+    } else {
+        k
+    };
+    (sub(f(c - 1), f(c)) + sub(f(c + 1), f(c))) / (h * h)
+}
+
+/// Value of `f` at node `k` of an axis with `n` nodes, smoothed according to the stencil.
+#[inline(always)]
+fn smooth<F>(n: usize, k: usize, stencil: GradientStencil, f: F) -> f64
+where
+    F: Fn(usize) -> f64,
+{
+    match stencil {
+        GradientStencil::Central => f(k),
+        GradientStencil::Sobel => {
+            let lo = if k > 0 { k - 1 } else { k };
+            let hi = if k + 1 < n { k + 1 } else { k };
+            0.25 * f(lo) + 0.5 * f(k) + 0.25 * f(hi)
+        }
+    }
+}
+
+/// Computes the gradient of `d` using central differences, see
+/// [`gradient_with`](fn.gradient_with.html).
+pub fn gradient(d: &[f64], dim: (usize, usize), h: f64) -> (Vec<f64>, Vec<f64>) {
+    gradient_with(d, dim, h, GradientStencil::Central)
+}
+
+/// Computes the `x` and `y` components of the gradient of `d` using the given stencil.
+///
+/// The gradient is not normalized. For a signed distance function its length is approximately 1,
+/// except at the kinks of the distance function (such as the center of a circle), so the length
+/// can serve as a quality check.
+///
+/// In a field without an interface, where all values are the same sentinel `±std::f64::MAX`, the
+/// gradient is zero.
+pub fn gradient_with(
+    d: &[f64],
+    dim: (usize, usize),
+    h: f64,
+    stencil: GradientStencil,
+) -> (Vec<f64>, Vec<f64>) {
+    let (ni, nj) = dim;
+    assert_eq!(ni * nj, d.len());
+    assert!(
+        ni >= 2 && nj >= 2,
+        "The array dimensions must be at least (2, 2), were ({}, {})",
+        ni,
+        nj
+    );
+
+    let at = |i: usize, j: usize| d[i * nj + j];
+    let mut gx = vec![0.; ni * nj];
+    let mut gy = vec![0.; ni * nj];
+    for i in 0..ni {
+        for j in 0..nj {
+            let s = i * nj + j;
+            gx[s] = diff(ni, i, h, |i| smooth(nj, j, stencil, |j| at(i, j)));
+            gy[s] = diff(nj, j, h, |j| smooth(ni, i, stencil, |i| at(i, j)));
+        }
+    }
+    (gx, gy)
+}
+
+/// Computes the magnitude of the gradient of `f` using central differences, see
+/// [`gradient`](fn.gradient.html).
+pub fn gradient_magnitude(f: &[f64], dim: (usize, usize), h: f64) -> Vec<f64> {
+    let (gx, gy) = gradient(f, dim, h);
+    gx.iter().zip(&gy).map(|(x, y)| x.hypot(*y)).collect()
+}
+
+/// Computes the unit normals of the level sets of `d`, the gradient computed with central
+/// differences (see [`gradient`](fn.gradient.html)) divided by its length.
+///
+/// Where the gradient vanishes, for instance in a field without an interface, the normal is zero.
+pub fn normals(d: &[f64], dim: (usize, usize), h: f64) -> (Vec<f64>, Vec<f64>) {
+    let (mut gx, mut gy) = gradient(d, dim, h);
+    for (x, y) in gx.iter_mut().zip(gy.iter_mut()) {
+        let g = x.hypot(*y);
+        if g > 0. {
+            *x /= g;
+            *y /= g;
+        }
+    }
+    (gx, gy)
+}
+
+/// Computes the curvature `κ = ∇·(∇d/|∇d|)` of the level sets of `d`, positive where the level
+/// sets are convex towards increasing `d` (for instance `1/r` for the distance from a circle).
+///
+/// The derivatives are approximated with central differences in the interior and with one-sided
+/// differences on the boundary, and combined as
+/// `κ = (d_xx d_y² - 2 d_x d_y d_xy + d_yy d_x²) / |∇d|³`. Where the gradient vanishes, for
+/// instance in a field without an interface, the curvature is zero.
+pub fn curvature(d: &[f64], dim: (usize, usize), h: f64) -> Vec<f64> {
+    let (ni, nj) = dim;
+    assert_eq!(ni * nj, d.len());
+    assert!(
+        ni >= 3 && nj >= 3,
+        "The array dimensions must be at least (3, 3), were ({}, {})",
+        ni,
+        nj
+    );
+
+    let at = |i: usize, j: usize| d[i * nj + j];
+    let mut k = vec![0.; ni * nj];
+    for i in 0..ni {
+        for j in 0..nj {
+            let dx = diff(ni, i, h, |i| at(i, j));
+            let dy = diff(nj, j, h, |j| at(i, j));
+            let g2 = dx * dx + dy * dy;
+            if g2 == 0. {
+                continue;
+            }
+            let dxx = second_diff(ni, i, h, |i| at(i, j));
+            let dyy = second_diff(nj, j, h, |j| at(i, j));
+            let dxy = diff(ni, i, h, |i| diff(nj, j, h, |j| at(i, j)));
+            k[i * nj + j] = (dxx * dy * dy - 2. * dx * dy * dxy + dyy * dx * dx) / (g2 * g2.sqrt());
+        }
+    }
+    k
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Exact signed distance function of a circle with some noise of amplitude `noise`.
+    fn circle(n: usize, h: f64, noise: f64) -> Vec<f64> {
+        let mut seed = 1u64;
+        let mut d = vec![0.; n * n];
+        for i in 0..n {
+            for j in 0..n {
+                seed = seed
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                let r = ((seed >> 11) as f64 / (1u64 << 53) as f64 - 0.5) * 2. * noise;
+                let (x, y) = (i as f64 * h - 0.5, j as f64 * h - 0.5);
+                d[i * n + j] = x.hypot(y) - 0.3 + r;
+            }
+        }
+        d
+    }
+
+    /// Mean squared deviation of the gradient from the radial direction, away from the center
+    /// and the boundary.
+    fn radial_error(gx: &[f64], gy: &[f64], n: usize, h: f64) -> f64 {
+        let (mut e, mut count) = (0., 0);
+        for i in 2..n - 2 {
+            for j in 2..n - 2 {
+                let (x, y) = (i as f64 * h - 0.5, j as f64 * h - 0.5);
+                let r = x.hypot(y);
+                if r > 0.1 {
+                    let s = i * n + j;
+                    e += (gx[s] - x / r).powi(2) + (gy[s] - y / r).powi(2);
+                    count += 1;
+                }
+            }
+        }
+        e / count as f64
+    }
+
+    #[test]
+    fn sobel_gradient_is_radial() {
+        let n = 41;
+        let h = 1. / (n - 1) as f64;
+        let d = circle(n, h, 0.);
+        let (gx, gy) = gradient_with(&d, (n, n), h, GradientStencil::Sobel);
+        assert!(radial_error(&gx, &gy, n, h) < 1e-3);
+        let (gx, gy) = gradient(&d, (n, n), h);
+        assert!(radial_error(&gx, &gy, n, h) < 1e-3);
+    }
+
+    #[test]
+    fn sobel_gradient_is_smoother() {
+        let n = 41;
+        let h = 1. / (n - 1) as f64;
+        let d = circle(n, h, 0.2 * h);
+        let (gx, gy) = gradient(&d, (n, n), h);
+        let central = radial_error(&gx, &gy, n, h);
+        let (gx, gy) = gradient_with(&d, (n, n), h, GradientStencil::Sobel);
+        let sobel = radial_error(&gx, &gy, n, h);
+        assert!(
+            sobel < 0.75 * central,
+            "sobel: {}, central: {}",
+            sobel,
+            central
+        );
+    }
+
+    #[test]
+    fn gradient_magnitude_of_circle_is_one() {
+        let n = 41;
+        let h = 1. / (n - 1) as f64;
+        let d = circle(n, h, 0.);
+        let g = gradient_magnitude(&d, (n, n), h);
+        for i in 2..n - 2 {
+            for j in 2..n - 2 {
+                let (x, y) = (i as f64 * h - 0.5, j as f64 * h - 0.5);
+                if x.hypot(y) > 0.1 {
+                    assert!((g[i * n + j] - 1.).abs() < 0.02, "{}", g[i * n + j]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn normals_have_unit_length() {
+        let n = 41;
+        let h = 1. / (n - 1) as f64;
+        // not a distance function, but with the same level sets as the circle
+        let d: Vec<f64> = circle(n, h, 0.).iter().map(|d| 3. * d).collect();
+        let (nx, ny) = normals(&d, (n, n), h);
+        for s in 0..n * n {
+            let len = nx[s].hypot(ny[s]);
+            assert!((len - 1.).abs() < 1e-12, "{}", len);
+        }
+        assert!(radial_error(&nx, &ny, n, h) < 1e-3);
+    }
+
+    #[test]
+    fn curvature_of_circle() {
+        let n = 41;
+        let h = 1. / (n - 1) as f64;
+        let d = circle(n, h, 0.);
+        let k = curvature(&d, (n, n), h);
+        for i in 1..n - 1 {
+            for j in 1..n - 1 {
+                let r = (i as f64 * h - 0.5).hypot(j as f64 * h - 0.5);
+                if r > 0.15 {
+                    let s = i * n + j;
+                    assert!((k[s] * r - 1.).abs() < 0.02, "{} != {}", k[s], 1. / r);
+                }
+            }
+        }
+        assert!(curvature(&vec![std::f64::MAX; 12], (3, 4), 0.5)
+            .iter()
+            .all(|&k| k == 0.));
+    }
+
+    #[test]
+    fn gradient_of_sentinels_is_zero() {
+        let d = vec![std::f64::MAX; 12];
+        for &stencil in &[GradientStencil::Central, GradientStencil::Sobel] {
+            let (gx, gy) = gradient_with(&d, (3, 4), 0.5, stencil);
+            assert!(gx.iter().chain(&gy).all(|&g| g == 0.));
+        }
+        let (nx, ny) = normals(&d, (3, 4), 0.5);
+        assert!(nx.iter().chain(&ny).all(|&n| n == 0.));
+    }
+}
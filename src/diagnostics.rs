@@ -0,0 +1,291 @@
+//! Diagnostics of the computed distance function.
+use super::signed_from_eikonal;
+use level_set::{init_dist_2d, triangle_dist};
+use {eikonal, DualNorm, EuclideanNorm};
+
+/// Computes the signed distance like [`signed_distance_2d`](../fn.signed_distance_2d.html) and a
+/// confidence in `(0, 1]` for every node.
+///
+/// The confidence is `1 / (1 + n)`, where `n` is the number of grid steps in the chain of upwind
+/// updates through which the value of the node was derived from the initialization near the
+/// interface. The nodes initialized directly have the confidence 1; the error of the first order
+/// scheme accumulates along the chain, so the values with a low confidence are less reliable.
+/// Along each axis the upwind neighbor is the one with the smaller distance, and the shortest
+/// chain through the upwind neighbors is taken.
+///
+/// The nodes that were not reached at all (no interface) have the confidence 0.
+pub fn signed_distance_2d_confidence(
+    d: &mut [f64],
+    confidence: &mut [f64],
+    u: &[f64],
+    dim: (usize, usize),
+    h: f64,
+) {
+    let (ni, nj) = dim;
+    assert_eq!(ni * nj, u.len());
+    assert_eq!(ni * nj, d.len());
+    assert_eq!(ni * nj, confidence.len());
+
+    init_dist_2d(d, u, dim, |p| EuclideanNorm.dual_norm(p));
+    // the lengths of the chains, `std::f64::MAX` where not known yet; they are whole numbers
+    let mut chain: Vec<f64> = d
+        .iter()
+        .map(|&d| if d < std::f64::MAX { 0. } else { std::f64::MAX })
+        .collect();
+    eikonal::fast_sweep_2d(d, dim, |d, v, s| EuclideanNorm.inv_dual_norm(d, v, s));
+
+    // d is strictly decreasing along the chains, so the sweeps terminate
+    eikonal::sweep_2d_repeat(
+        &mut chain,
+        dim,
+        0.,
+        |_| false,
+        |chain, s, (i, j), _| {
+            let axes = [(i > 0, i + 1 < ni, nj), (j > 0, j + 1 < nj, 1)];
+            let mut best = chain[s];
+            for &(has_lo, has_hi, stride) in &axes {
+                let k = match (has_lo, has_hi) {
+                    (true, true) if d[s + stride] < d[s - stride] => s + stride,
+                    (true, _) => s - stride,
+                    (false, true) => s + stride,
+                    (false, false) => continue,
+                };
+                if d[k] < d[s] && chain[k] < std::f64::MAX {
+                    best = best.min(chain[k] + 1.);
+                }
+            }
+            best
+        },
+    );
+
+    for (c, &n) in confidence.iter_mut().zip(&chain) {
+        *c = if n == std::f64::MAX {
+            0.
+        } else {
+            1. / (1. + n)
+        };
+    }
+    signed_from_eikonal(d, u, h);
+}
+
+/// Follows the characteristic of the distance function `d` on a regular 2D grid with spacing `h`
+/// from the node `start` back to the interface.
+///
+/// The path moves in each step to the horizontal, vertical or diagonal neighbor in which `|d|`
+/// decreases the fastest, that is, along the upwind direction of the solution. It ends at a node
+/// where `d` is zero, where a horizontal or vertical neighbor has the opposite sign (the interface
+/// is crossed in between) or where `|d|` cannot decrease anymore. The returned path starts with
+/// `start` and ends with that node.
+pub fn characteristic_path(
+    d: &[f64],
+    dim: (usize, usize),
+    h: f64,
+    start: (usize, usize),
+) -> Vec<(usize, usize)> {
+    let (ni, nj) = dim;
+    assert_eq!(ni * nj, d.len());
+    assert!(start.0 < ni && start.1 < nj);
+
+    let mut path = vec![start];
+    let (mut i, mut j) = start;
+    loop {
+        let s = i * nj + j;
+        let crosses = |k: usize| (d[k] < 0.) != (d[s] < 0.);
+        if d[s] == 0.
+            || (i > 0 && crosses(s - nj))
+            || (i + 1 < ni && crosses(s + nj))
+            || (j > 0 && crosses(s - 1))
+            || (j + 1 < nj && crosses(s + 1))
+        {
+            return path;
+        }
+        let mut best = None;
+        let mut steepest = 0.;
+        for di in -1isize..2 {
+            for dj in -1isize..2 {
+                let (a, b) = (i as isize + di, j as isize + dj);
+                if (di, dj) == (0, 0) || a < 0 || b < 0 || a >= ni as isize || b >= nj as isize {
+                    continue;
+                }
+                let (a, b) = (a as usize, b as usize);
+                let slope =
+                    (d[s].abs() - d[a * nj + b].abs()) / (h * ((di * di + dj * dj) as f64).sqrt());
+                if slope > steepest {
+                    steepest = slope;
+                    best = Some((a, b));
+                }
+            }
+        }
+        match best {
+            Some(next) => {
+                path.push(next);
+                i = next.0;
+                j = next.1;
+            }
+            None => return path,
+        }
+    }
+}
+
+/// Initializes the distance function near the free boundary like
+/// [`init_dist_2d`](../level_set/fn.init_dist_2d.html) and records in `triangle` the id of the
+/// triangle that produced the (minimal) value of every node.
+///
+/// The square with the vertices `(i - 1, j - 1)` to `(i, j)` has the number
+/// `q = (i - 1) (nj - 1) + (j - 1)`; its triangle with the vertices `(i - 1, j - 1)`,
+/// `(i - 1, j)`, `(i, j)` has the id `2 q` and the triangle with the vertices `(i - 1, j - 1)`,
+/// `(i, j - 1)`, `(i, j)` has the id `2 q + 1`, see
+/// [`triangle_vertices`](fn.triangle_vertices.html). The nodes away from the interface get the id
+/// `std::usize::MAX`.
+///
+/// The values in `d` are the same as those computed by `init_dist_2d`.
+pub fn init_dist_2d_traced<F>(
+    d: &mut [f64],
+    triangle: &mut [usize],
+    u: &[f64],
+    dim: (usize, usize),
+    mut dual_norm: F,
+) where
+    F: FnMut([f64; 2]) -> f64,
+{
+    let (ni, nj) = dim;
+    assert_eq!(ni * nj, u.len());
+    assert_eq!(ni * nj, d.len());
+    assert_eq!(ni * nj, triangle.len());
+
+    for (d, t) in d.iter_mut().zip(triangle.iter_mut()) {
+        *d = std::f64::MAX;
+        *t = std::usize::MAX;
+    }
+
+    for i in 1..ni {
+        for j in 1..nj {
+            let q = (i - 1) * (nj - 1) + (j - 1);
+            for k in 0..2 {
+                let id = 2 * q + k;
+                let v = triangle_vertices(id, dim);
+                let perm = if k == 0 { [1, 0] } else { [0, 1] };
+                if let Some(e) = triangle_dist([u[v[0]], u[v[1]], u[v[2]]], perm, &mut dual_norm) {
+                    for (&v, &e) in v.iter().zip(&e) {
+                        // init_dist_2d keeps the later value on ties
+                        if e <= d[v] {
+                            d[v] = e;
+                            triangle[v] = id;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Returns the indices of the vertices of the triangle with the id `id`, see
+/// [`init_dist_2d_traced`](fn.init_dist_2d_traced.html).
+pub fn triangle_vertices(id: usize, dim: (usize, usize)) -> [usize; 3] {
+    let nj = dim.1;
+    let q = id / 2;
+    let (i, j) = (q / (nj - 1) + 1, q % (nj - 1) + 1);
+    let s = i * nj + j;
+    if id & 1 == 0 {
+        [s - nj - 1, s - nj, s]
+    } else {
+        [s - nj - 1, s - 1, s]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn traced_triangles_contain_interface() {
+        let n = 25;
+        let h = 1. / (n - 1) as f64;
+        let mut u = vec![0.; n * n];
+        for i in 0..n {
+            for j in 0..n {
+                let (x, y) = (i as f64 * h - 0.45, j as f64 * h - 0.55);
+                u[i * n + j] = x.hypot(y) - 0.3;
+            }
+        }
+        let norm = |p| EuclideanNorm.dual_norm(p);
+        let mut d = vec![0.; n * n];
+        let mut tri = vec![0; n * n];
+        init_dist_2d_traced(&mut d, &mut tri, &u, (n, n), norm);
+        let mut expected = vec![0.; n * n];
+        init_dist_2d(&mut expected, &u, (n, n), norm);
+        assert_eq!(d, expected);
+
+        let mut traced = 0;
+        for s in 0..n * n {
+            if tri[s] == std::usize::MAX {
+                assert_eq!(d[s], std::f64::MAX);
+                continue;
+            }
+            traced += 1;
+            let v = triangle_vertices(tri[s], (n, n));
+            assert!(v.contains(&s));
+            // the interface passes through the triangle
+            assert!(v.iter().any(|&v| u[v] < 0.) && v.iter().any(|&v| u[v] >= 0.));
+        }
+        assert!(traced > 50);
+    }
+
+    #[test]
+    fn path_leads_back_to_seed() {
+        let n = 31;
+        let h = 1. / (n - 1) as f64;
+        let c = 12;
+        let mut d = vec![std::f64::MAX; n * n];
+        d[c * n + c] = 0.;
+        eikonal::fast_sweep_2d(&mut d, (n, n), |d, v, s| {
+            EuclideanNorm.inv_dual_norm(d, v, s)
+        });
+        for d in &mut d {
+            *d *= h;
+        }
+
+        let path = characteristic_path(&d, (n, n), h, (c + 15, c));
+        let straight: Vec<_> = (0..16).rev().map(|k| (c + k, c)).collect();
+        assert_eq!(path, straight);
+        let path = characteristic_path(&d, (n, n), h, (c - 8, c - 8));
+        let diagonal: Vec<_> = (0..9).rev().map(|k| (c - k, c - k)).collect();
+        assert_eq!(path, diagonal);
+        assert_eq!(characteristic_path(&d, (n, n), h, (c, c)), [(c, c)]);
+    }
+
+    #[test]
+    fn confidence_decays_away_from_interface() {
+        let n = 41;
+        let h = 1. / (n - 1) as f64;
+        let mut u = vec![0.; n * n];
+        for i in 0..n {
+            for j in 0..n {
+                let (x, y) = (i as f64 * h - 0.25, j as f64 * h - 0.5);
+                u[i * n + j] = x.hypot(y) - 0.1;
+            }
+        }
+        let mut d = vec![0.; n * n];
+        let mut c = vec![0.; n * n];
+        signed_distance_2d_confidence(&mut d, &mut c, &u, (n, n), h);
+
+        let mut expected = vec![0.; n * n];
+        ::signed_distance_2d(&mut expected, &u, (n, n), h);
+        assert_eq!(d, expected);
+
+        // along the row j = n / 2, away from the circle
+        let row: Vec<f64> = (14..n).map(|i| c[i * n + n / 2]).collect();
+        assert_eq!(row[0], 1.);
+        for w in row.windows(2) {
+            assert!(w[1] <= w[0]);
+        }
+        assert!(row[row.len() - 1] < 0.1);
+        assert!(c.iter().all(|&c| c > 0. && c <= 1.));
+
+        let u = vec![1.; 9];
+        let mut d = vec![0.; 9];
+        let mut c = vec![1.; 9];
+        signed_distance_2d_confidence(&mut d, &mut c, &u, (3, 3), h);
+        assert!(c.iter().all(|&c| c == 0.));
+    }
+}
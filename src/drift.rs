@@ -0,0 +1,142 @@
+//! Travel times with a drift, such as wind or a current (Zermelo's navigation problem).
+//!
+//! A vehicle moves with the speed `c` relative to a medium that itself moves with the velocity
+//! `b`, so the set of the achievable velocities is the disk of radius `c` centered at `b`. The
+//! first arrival time `T` from the sources solves the static Hamilton-Jacobi-Bellman equation
+//!
+//! c |∇T| + b · ∇T = 1,
+//!
+//! which is an eikonal equation for an asymmetric (non-even) norm. Therefore the information does
+//! not travel along straight lines with the same speed in all directions, and a node can be
+//! reached from any of the four quadrants around it.
+use super::min;
+use eikonal;
+
+/// Time needed to travel by the displacement `y` with the speed `c` and the drift `b`.
+#[inline(always)]
+fn travel_time(y: [f64; 2], c: f64, b: [f64; 2]) -> f64 {
+    let yb = y[0] * b[0] + y[1] * b[1];
+    let y2 = y[0] * y[0] + y[1] * y[1];
+    let k = c * c - (b[0] * b[0] + b[1] * b[1]);
+    ((yb * yb + k * y2).sqrt() - yb) / k
+}
+
+/// Semi-Lagrangian update from the quadrant spanned by the neighbors with the values `ta` at the
+/// displacement `a` and `tb` at the displacement `b` from the node.
+fn quadrant_update(ta: f64, a: [f64; 2], tb: f64, b: [f64; 2], c: f64, w: [f64; 2]) -> f64 {
+    let f = |l: f64| {
+        let z = [(1. - l) * a[0] + l * b[0], (1. - l) * a[1] + l * b[1]];
+        (1. - l) * ta + l * tb + travel_time([-z[0], -z[1]], c, w)
+    };
+    match (ta < std::f64::MAX, tb < std::f64::MAX) {
+        (false, false) => std::f64::MAX,
+        (true, false) => f(0.),
+        (false, true) => f(1.),
+        (true, true) => {
+            // the function is convex in l: golden section search
+            let g = 0.5 * (5f64.sqrt() - 1.);
+            let (mut lo, mut hi) = (0., 1.);
+            for _ in 0..40 {
+                let (p, q) = (hi - g * (hi - lo), lo + g * (hi - lo));
+                if f(p) < f(q) {
+                    hi = q;
+                } else {
+                    lo = p;
+                }
+            }
+            min(min(f(0.), f(1.)), f(0.5 * (lo + hi)))
+        }
+    }
+}
+
+/// Computes the first arrival times `t` on a regular 2D grid with spacing `h` for the speed
+/// `speed` and the drift `drift` given at the nodes.
+///
+/// `t` should be initialized to the arrival time at the sources (usually zero) and to
+/// `std::f64::MAX` elsewhere. The speed must be larger than the magnitude of the drift at every
+/// node, otherwise some directions cannot be traveled at all.
+///
+/// The sweeps in the four directions are repeated until `t` does not change anymore.
+pub fn travel_time_drift_2d(
+    t: &mut [f64],
+    dim: (usize, usize),
+    h: f64,
+    speed: &[f64],
+    drift: &[[f64; 2]],
+) {
+    let (ni, nj) = dim;
+    assert_eq!(ni * nj, t.len());
+    assert_eq!(ni * nj, speed.len());
+    assert_eq!(ni * nj, drift.len());
+    for (&c, b) in speed.iter().zip(drift) {
+        assert!(
+            b[0].hypot(b[1]) < c,
+            "The speed {} must be larger than the drift {:?}",
+            c,
+            b
+        );
+    }
+
+    // every node is updated from all four quadrants, whatever the direction of the sweep
+    eikonal::sweep_2d_repeat(
+        t,
+        dim,
+        0.,
+        |_| false,
+        |t, s, (i, j), _| {
+            let (c, b) = (speed[s], drift[s]);
+            let mut best = t[s];
+            for &di in &[-1isize, 1] {
+                let a = i as isize + di;
+                let ta = if a >= 0 && a < ni as isize {
+                    t[a as usize * nj + j]
+                } else {
+                    std::f64::MAX
+                };
+                for &dj in &[-1isize, 1] {
+                    let bj = j as isize + dj;
+                    let tb = if bj >= 0 && bj < nj as isize {
+                        t[i * nj + bj as usize]
+                    } else {
+                        std::f64::MAX
+                    };
+                    let r = quadrant_update(ta, [di as f64 * h, 0.], tb, [0., dj as f64 * h], c, b);
+                    best = min(best, r);
+                }
+            }
+            best
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn downwind_is_faster() {
+        let n = 41;
+        let h = 1. / (n - 1) as f64;
+        let c = n / 2;
+        let speed = vec![1.; n * n];
+        let wind = [0.5, 0.];
+        let drift = vec![wind; n * n];
+        let mut t = vec![std::f64::MAX; n * n];
+        t[c * n + c] = 0.;
+        travel_time_drift_2d(&mut t, (n, n), h, &speed, &drift);
+
+        let (down, up) = (t[(c + 10) * n + c], t[(c - 10) * n + c]);
+        assert!((down - 0.25 / 1.5).abs() < 1e-9, "{}", down);
+        assert!((up - 0.25 / 0.5).abs() < 1e-9, "{}", up);
+
+        // compare with the exact travel times
+        for i in 0..n {
+            for j in 0..n {
+                let y = [(i as f64 - c as f64) * h, (j as f64 - c as f64) * h];
+                let e = travel_time(y, 1., wind);
+                assert!(t[i * n + j] >= e - 1e-9);
+                assert!(t[i * n + j] <= e + 0.05, "{} != {}", t[i * n + j], e);
+            }
+        }
+    }
+}
@@ -9,10 +9,16 @@
 //! origin), by performing 2^N Gauss-Seidel sweeps in alternating directions. Here N is the
 //! dimension. Zhao showed that the accuracy for the Euclidean norm is O(h log h).
 
+use super::{max, min};
+use error::{Preflight, SweepError};
 use ndarray::prelude::*;
 use ndarray::{azip, s};
-// use ndarray_parallel::par_azip;
+#[cfg(feature = "parallel")]
+use ndarray_parallel::par_azip;
+use norm::{AffineNorm, DualNorm, EuclideanNorm};
+use num_traits::Float;
 use std::cmp;
+use std::collections::BinaryHeap;
 
 /// Computes the solution of the eikonal equation ‖∇d‖ = 1 in 2D using the fast sweeping algorithm.
 ///
@@ -24,6 +30,49 @@ use std::cmp;
 pub fn fast_sweep_2d<F>(d: &mut [f64], dim: (usize, usize), inv_norm: F)
 where
     F: Fn(f64, [f64; 2], [f64; 2]) -> f64,
+{
+    fast_sweep_2d_float(d, dim, inv_norm)
+}
+
+/// Computes the solution of the eikonal equation like [`fast_sweep_2d`](fn.fast_sweep_2d.html)
+/// for any floating point type `T`, for instance `f32` to halve the memory traffic.
+pub fn fast_sweep_2d_float<T, F>(d: &mut [T], dim: (usize, usize), inv_norm: F)
+where
+    T: Float,
+    F: Fn(T, [T; 2], [T; 2]) -> T,
+{
+    sweep_bands_2d(d, dim, &inv_norm, |mut out, di, dj, s| {
+        azip!(mut out, di, dj in { *out = inv_norm(*out, [di, dj], s) });
+    });
+}
+
+/// Computes the solution of the eikonal equation like [`fast_sweep_2d`](fn.fast_sweep_2d.html),
+/// updating the nodes of every diagonal band in parallel.
+///
+/// The nodes of a band depend only on the previous band in the direction of the sweep, so they are
+/// independent of each other and the result is identical to that of `fast_sweep_2d`. The bands
+/// are processed one after another, so the speedup grows with the length of the bands; it pays
+/// off only for large grids.
+///
+/// Available with the `parallel` feature.
+#[cfg(feature = "parallel")]
+pub fn fast_sweep_2d_par<F>(d: &mut [f64], dim: (usize, usize), inv_norm: F)
+where
+    F: Fn(f64, [f64; 2], [f64; 2]) -> f64 + Sync,
+{
+    sweep_bands_2d(d, dim, &inv_norm, |mut out, di, dj, s| {
+        par_azip!(mut out, di, dj in { *out = inv_norm(*out, [di, dj], s) });
+    });
+}
+
+/// The sweeps of [`fast_sweep_2d`](fn.fast_sweep_2d.html) in the four directions, with the nodes
+/// of a diagonal band updated by `band(out, di, dj, s)` from their upwind neighbors along the
+/// axes `di` and `dj` in the directions `s`.
+fn sweep_bands_2d<T, F, B>(d: &mut [T], dim: (usize, usize), inv_norm: &F, mut band: B)
+where
+    T: Float,
+    F: Fn(T, [T; 2], [T; 2]) -> T,
+    B: FnMut(ArrayViewMut1<T>, ArrayView1<T>, ArrayView1<T>, [T; 2]),
 {
     let (ni, nj) = dim;
     assert_eq!(ni * nj, d.len());
@@ -37,31 +86,10 @@ where
 
     // array strides
     let (si, _sj) = (nj, 1);
+    let (one, sign) = (T::one(), |s: i32| if s == 1 { T::one() } else { -T::one() });
     // sweep in 4 directions
 
-    // Propagate information along the edges.
-    for p in 1..nj {
-        let s = p;
-        d[s] = inv_norm(d[s], [std::f64::MAX, d[s - 1]], [1., 1.]);
-        let s = (ni - 1) * si + p;
-        d[s] = inv_norm(d[s], [std::f64::MAX, d[s - 1]], [-1., 1.]);
-        let p = nj - 1 - p;
-        let s = p;
-        d[s] = inv_norm(d[s], [std::f64::MAX, d[s + 1]], [1., -1.]);
-        let s = (ni - 1) * si + p;
-        d[s] = inv_norm(d[s], [std::f64::MAX, d[s + 1]], [-1., -1.]);
-    }
-    for p in 1..ni {
-        let s = p * si;
-        d[s] = inv_norm(d[s], [d[s - si], std::f64::MAX], [1., 1.]);
-        let s = p * si + nj - 1;
-        d[s] = inv_norm(d[s], [d[s - si], std::f64::MAX], [1., -1.]);
-        let p = ni - 1 - p;
-        let s = p * si;
-        d[s] = inv_norm(d[s], [d[s + si], std::f64::MAX], [-1., 1.]);
-        let s = p * si + nj - 1;
-        d[s] = inv_norm(d[s], [d[s + si], std::f64::MAX], [-1., -1.]);
-    }
+    sweep_edges_2d(d, dim, nj, inv_norm);
 
     // We sweep in diagonal bands to take advantage of an instruction-level parallelism. This also
     // allows for potential parallelization.
@@ -136,8 +164,8 @@ where
             let offset = if $idir == 1 { 1 } else { 0 };
             let di = input.slice(s![1 - offset..len - offset, 0]);
             let dj = input.slice(s![offset..len - 1  + offset, 0]);
-            let mut out = output.slice_mut(s![offset..len -1 + offset, 0]);
-            azip!(mut out, di, dj in { *out = inv_norm(*out, [di, dj], [$idir as f64, $jdir as f64])});
+            let out = output.slice_mut(s![offset..len -1 + offset, 0]);
+            band(out, di, dj, [sign($idir), sign($jdir)]);
         }
     }
 
@@ -154,7 +182,7 @@ where
         let (mut ci, mut cj, mut len) = band_minus(band);
         if ci == 0 && cj == 0 {
             // special handling of corner at (0, 0)
-            d[si] = inv_norm(d[si], [d[0], d[si + 1]], [1., -1.]);
+            d[si] = inv_norm(d[si], [d[0], d[si + 1]], [one, -one]);
             ci += 1;
             cj += 1;
             len -= 1;
@@ -167,13 +195,707 @@ where
         if band == nj - 1 {
             // special handling of corner at (ni - 1, nj - 1)
             let s = (ni - 2) * si + nj - 1;
-            d[s] = inv_norm(d[s], [d[s + si], d[s - 1]], [-1., 1.]);
+            d[s] = inv_norm(d[s], [d[s + si], d[s - 1]], [-one, one]);
             len -= 1;
         }
         band_sweep!((ci, cj, len), (-1, 1));
     }
 }
 
+/// Propagates the information along the edges of the grid in all four directions, the first
+/// step of [`fast_sweep_2d`](fn.fast_sweep_2d.html). The rows start `stride` values apart in `d`.
+fn sweep_edges_2d<T, F>(d: &mut [T], dim: (usize, usize), stride: usize, inv_norm: &F)
+where
+    T: Float,
+    F: Fn(T, [T; 2], [T; 2]) -> T,
+{
+    let (ni, nj) = dim;
+    let si = stride;
+    let (one, max) = (T::one(), T::max_value());
+    // Propagate information along the edges.
+    for p in 1..nj {
+        let s = p;
+        d[s] = inv_norm(d[s], [max, d[s - 1]], [one, one]);
+        let s = (ni - 1) * si + p;
+        d[s] = inv_norm(d[s], [max, d[s - 1]], [-one, one]);
+        let p = nj - 1 - p;
+        let s = p;
+        d[s] = inv_norm(d[s], [max, d[s + 1]], [one, -one]);
+        let s = (ni - 1) * si + p;
+        d[s] = inv_norm(d[s], [max, d[s + 1]], [-one, -one]);
+    }
+    for p in 1..ni {
+        let s = p * si;
+        d[s] = inv_norm(d[s], [d[s - si], max], [one, one]);
+        let s = p * si + nj - 1;
+        d[s] = inv_norm(d[s], [d[s - si], max], [one, -one]);
+        let p = ni - 1 - p;
+        let s = p * si;
+        d[s] = inv_norm(d[s], [d[s + si], max], [-one, one]);
+        let s = p * si + nj - 1;
+        d[s] = inv_norm(d[s], [d[s + si], max], [-one, -one]);
+    }
+}
+
+/// Computes the same solution as [`fast_sweep_2d`](fn.fast_sweep_2d.html), bit for bit, on a
+/// grid whose rows start `stride` values apart in `d`; the values between the rows are neither
+/// read nor written.
+///
+/// The sweeps visit the nodes row by row instead of in diagonal bands, which does not change the
+/// result since every node depends only on the nodes before it in the direction of the sweep.
+pub(crate) fn fast_sweep_2d_strided<F>(
+    d: &mut [f64],
+    dim: (usize, usize),
+    stride: usize,
+    inv_norm: F,
+) where
+    F: Fn(f64, [f64; 2], [f64; 2]) -> f64,
+{
+    let (ni, nj) = dim;
+    assert!(
+        ni >= 3 && nj >= 3,
+        "The array dimensions must be at least (3, 3), were ({}, {})",
+        ni,
+        nj
+    );
+    assert!(stride >= nj && d.len() >= (ni - 1) * stride + nj);
+
+    sweep_edges_2d(d, dim, stride, &inv_norm);
+    for &dir in &SWEEP_DIRS_2D {
+        // the first row and column in the direction of the sweep are done by the edges
+        let first = (
+            if dir.0 == 1 { 0 } else { ni - 1 },
+            if dir.1 == 1 { 0 } else { nj - 1 },
+        );
+        sweep_2d_strided(
+            d,
+            dim,
+            stride,
+            dir,
+            |s| s / stride == first.0 || s % stride == first.1,
+            |d, s, _, (si, sj)| {
+                let vi = d[(s as isize - si * stride as isize) as usize];
+                let vj = d[(s as isize - sj) as usize];
+                inv_norm(d[s], [vi, vj], [si as f64, sj as f64])
+            },
+        );
+    }
+}
+
+/// Computes the solution of the eikonal equation ‖∇d‖ = 1 in 2D by Gauss-Seidel sweeps with a
+/// user-defined stopping rule.
+///
+/// The arguments are the same as for [`fast_sweep_2d`](fn.fast_sweep_2d.html). The sweeps cycle
+/// through the directions `(1, 1)`, `(-1, -1)`, `(1, -1)`, `(-1, 1)`, and after each sweep
+/// `stop(d, k)` is called with the current values and the index `k` of the sweep (starting at 0).
+/// The iteration ends when `stop` returns `true` or when four consecutive sweeps do not change
+/// anything, that is, when the solution has converged.
+///
+/// Returns the number of sweeps performed.
+pub fn fast_sweep_2d_until<F, P>(
+    d: &mut [f64],
+    dim: (usize, usize),
+    inv_norm: F,
+    mut stop: P,
+) -> usize
+where
+    F: Fn(f64, [f64; 2], [f64; 2]) -> f64,
+    P: FnMut(&[f64], usize) -> bool,
+{
+    let (ni, nj) = dim;
+    assert_eq!(ni * nj, d.len());
+
+    let dirs = SWEEP_DIRS_2D;
+    let mut unchanged = 0;
+    let mut k = 0;
+    while unchanged < dirs.len() {
+        let change = sweep_2d(
+            d,
+            dim,
+            dirs[k % dirs.len()],
+            |_| false,
+            |d, s, ij, dir| norm_update(&inv_norm, d, dim, s, ij, dir),
+        );
+        unchanged = if change > 0. { 0 } else { unchanged + 1 };
+        k += 1;
+        if stop(d, k - 1) {
+            break;
+        }
+    }
+    k
+}
+
+/// Computes the solution of the eikonal equation ‖∇d‖ = 1 in 2D like
+/// [`fast_sweep_2d_until`](fn.fast_sweep_2d_until.html), reporting the progress.
+///
+/// After each sweep `progress(k, change)` is called with the index `k` of the sweep (starting at
+/// 0) and the largest change of a value in it, the residual of the iteration. The change is
+/// infinite while the sweeps still reach nodes that were `std::f64::MAX`. The iteration ends when
+/// four consecutive sweeps do not change anything, so the last four calls report a zero change.
+/// The numerics are the same as those of `fast_sweep_2d_until`; the callback only gives a hook for
+/// a progress bar or for logging the decay of the residual.
+///
+/// Returns the number of sweeps performed.
+pub fn fast_sweep_2d_progress<F, P>(
+    d: &mut [f64],
+    dim: (usize, usize),
+    inv_norm: F,
+    mut progress: P,
+) -> usize
+where
+    F: Fn(f64, [f64; 2], [f64; 2]) -> f64,
+    P: FnMut(usize, f64),
+{
+    let (ni, nj) = dim;
+    assert_eq!(ni * nj, d.len());
+
+    let dirs = SWEEP_DIRS_2D;
+    let mut unchanged = 0;
+    let mut k = 0;
+    while unchanged < dirs.len() {
+        let change = sweep_2d(
+            d,
+            dim,
+            dirs[k % dirs.len()],
+            |_| false,
+            |d, s, ij, dir| norm_update(&inv_norm, d, dim, s, ij, dir),
+        );
+        unchanged = if change > 0. { 0 } else { unchanged + 1 };
+        progress(k, change);
+        k += 1;
+    }
+    k
+}
+
+/// The directions of the Gauss-Seidel sweeps over a 2D grid in the order in which they are
+/// performed, by the signs of the steps of the indices `(i, j)`.
+pub(crate) const SWEEP_DIRS_2D: [(isize, isize); 4] = [(1, 1), (-1, -1), (1, -1), (-1, 1)];
+
+/// Visits the nodes of a grid of dimensions `dim`, whose rows start `stride` values apart, in the
+/// order of the sweep in the direction `(si, sj)`, calling `visit(s, (i, j))` with the index
+/// `s = i * stride + j` of the node `(i, j)`. The upwind neighbors of a node are visited before it.
+pub(crate) fn visit_2d<V>(
+    dim: (usize, usize),
+    stride: usize,
+    (si, sj): (isize, isize),
+    mut visit: V,
+) where
+    V: FnMut(usize, (usize, usize)),
+{
+    let (ni, nj) = dim;
+    for p in 0..ni {
+        let i = if si == 1 { p } else { ni - 1 - p };
+        for q in 0..nj {
+            let j = if sj == 1 { q } else { nj - 1 - q };
+            visit(i * stride + j, (i, j));
+        }
+    }
+}
+
+/// Performs a single Gauss-Seidel sweep over the grid of dimensions `dim` in the direction
+/// `(si, sj)` and returns the largest change of a value, infinite if a node got its first value.
+///
+/// The nodes `s` where `fixed(s)` is true keep their values. At every other node `(i, j)` with
+/// the index `s` in `d`, the candidate `update(d, s, (i, j), (si, sj))` replaces the value if it
+/// is smaller.
+pub(crate) fn sweep_2d<X, U>(
+    d: &mut [f64],
+    dim: (usize, usize),
+    dir: (isize, isize),
+    fixed: X,
+    update: U,
+) -> f64
+where
+    X: Fn(usize) -> bool,
+    U: FnMut(&[f64], usize, (usize, usize), (isize, isize)) -> f64,
+{
+    sweep_2d_strided(d, dim, dim.1, dir, fixed, update)
+}
+
+/// Performs a single sweep like [`sweep_2d`](fn.sweep_2d.html) on a grid whose rows start
+/// `stride` values apart in `d`, so that the node `(i, j)` has the index `s = i * stride + j`.
+pub(crate) fn sweep_2d_strided<X, U>(
+    d: &mut [f64],
+    dim: (usize, usize),
+    stride: usize,
+    dir: (isize, isize),
+    fixed: X,
+    mut update: U,
+) -> f64
+where
+    X: Fn(usize) -> bool,
+    U: FnMut(&[f64], usize, (usize, usize), (isize, isize)) -> f64,
+{
+    let mut change = 0.;
+    visit_2d(dim, stride, dir, |s, ij| {
+        if fixed(s) {
+            return;
+        }
+        let t = update(d, s, ij, dir);
+        if t < d[s] {
+            change = if d[s] == std::f64::MAX {
+                std::f64::INFINITY
+            } else {
+                max(change, d[s] - t)
+            };
+            d[s] = t;
+        }
+    });
+    change
+}
+
+/// Repeats the sweeps of [`sweep_2d`](fn.sweep_2d.html) in the four directions until the largest
+/// change of a value over a round of them is at most `tol`.
+pub(crate) fn sweep_2d_repeat<X, U>(
+    d: &mut [f64],
+    dim: (usize, usize),
+    tol: f64,
+    fixed: X,
+    mut update: U,
+) where
+    X: Fn(usize) -> bool,
+    U: FnMut(&[f64], usize, (usize, usize), (isize, isize)) -> f64,
+{
+    loop {
+        let mut change = 0.;
+        for &dir in &SWEEP_DIRS_2D {
+            change = max(change, sweep_2d(d, dim, dir, &fixed, &mut update));
+        }
+        if change <= tol {
+            break;
+        }
+    }
+}
+
+/// The values of the upwind neighbors along the two axes of the node `(i, j)` with the index `s`
+/// for the sweep in the direction `(si, sj)`, `std::f64::MAX` outside of the grid and at the nodes
+/// where `blocked` is true.
+pub(crate) fn upwind_2d<B>(
+    d: &[f64],
+    dim: (usize, usize),
+    s: usize,
+    (i, j): (usize, usize),
+    (si, sj): (isize, isize),
+    blocked: B,
+) -> [f64; 2]
+where
+    B: Fn(usize) -> bool,
+{
+    let (ni, nj) = dim;
+    let value = |t: usize| if blocked(t) { std::f64::MAX } else { d[t] };
+    let vi = if si == 1 && i > 0 {
+        value(s - nj)
+    } else if si == -1 && i + 1 < ni {
+        value(s + nj)
+    } else {
+        std::f64::MAX
+    };
+    let vj = if sj == 1 && j > 0 {
+        value(s - 1)
+    } else if sj == -1 && j + 1 < nj {
+        value(s + 1)
+    } else {
+        std::f64::MAX
+    };
+    [vi, vj]
+}
+
+/// The update of [`fast_sweep_2d`](fn.fast_sweep_2d.html) by `inv_norm` for
+/// [`sweep_2d`](fn.sweep_2d.html).
+fn norm_update<F>(
+    inv_norm: &F,
+    d: &[f64],
+    dim: (usize, usize),
+    s: usize,
+    ij: (usize, usize),
+    dir: (isize, isize),
+) -> f64
+where
+    F: Fn(f64, [f64; 2], [f64; 2]) -> f64,
+{
+    let v = upwind_2d(d, dim, s, ij, dir, |_| false);
+    inv_norm(d[s], v, [dir.0 as f64, dir.1 as f64])
+}
+
+/// A direction of a Gauss-Seidel sweep over a 2D grid, by the signs of the steps of the indices
+/// `(i, j)`. The upwind neighbors of a node are the ones visited before it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SweepDir {
+    /// `i` and `j` increasing.
+    PlusPlus,
+    /// `i` and `j` decreasing.
+    MinusMinus,
+    /// `i` increasing and `j` decreasing.
+    PlusMinus,
+    /// `i` decreasing and `j` increasing.
+    MinusPlus,
+}
+
+impl SweepDir {
+    /// The standard cycle of the four sweeps, in the order of
+    /// [`fast_sweep_2d_until`](fn.fast_sweep_2d_until.html).
+    pub const ALL: [SweepDir; 4] = [
+        SweepDir::PlusPlus,
+        SweepDir::MinusMinus,
+        SweepDir::PlusMinus,
+        SweepDir::MinusPlus,
+    ];
+
+    /// The signs of the steps of `i` and `j`.
+    pub fn steps(self) -> (isize, isize) {
+        match self {
+            SweepDir::PlusPlus => (1, 1),
+            SweepDir::MinusMinus => (-1, -1),
+            SweepDir::PlusMinus => (1, -1),
+            SweepDir::MinusPlus => (-1, 1),
+        }
+    }
+}
+
+/// Computes the Euclidean distance ‖∇d‖₂ = 1 in 2D in grid units by sweeping in the directions of
+/// `order`.
+///
+/// `d` should be initialized near the interface and to `std::f64::MAX` elsewhere. The sweeps cycle
+/// through `order`, and the iteration ends as soon as every direction of `order` has run once
+/// without changing anything, so a direction may repeat in `order` and a subset of the directions
+/// gives the solution restricted to the characteristics along them. With
+/// [`SweepDir::ALL`](enum.SweepDir.html#associatedconstant.ALL) the result is the converged one of
+/// [`fast_sweep_2d_until`](fn.fast_sweep_2d_until.html), and if a single round of the four sweeps
+/// suffices, 8 sweeps are performed, the last 4 confirming the convergence.
+///
+/// Returns the number of sweeps performed.
+pub fn fast_sweep_dist_with_order(d: &mut [f64], dim: (usize, usize), order: &[SweepDir]) -> usize {
+    let (ni, nj) = dim;
+    assert_eq!(ni * nj, d.len());
+    assert!(!order.is_empty(), "At least one sweep direction is needed");
+
+    let inv_norm = |d: f64, v: [f64; 2], s: [f64; 2]| EuclideanNorm.inv_dual_norm(d, v, s);
+    let mut unchanged = 0;
+    let mut k = 0;
+    while unchanged < order.len() {
+        let change = sweep_2d(
+            d,
+            dim,
+            order[k % order.len()].steps(),
+            |_| false,
+            |d, s, ij, dir| norm_update(&inv_norm, d, dim, s, ij, dir),
+        );
+        unchanged = if change > 0. { 0 } else { unchanged + 1 };
+        k += 1;
+    }
+    k
+}
+
+/// Computes the solution of the eikonal equation ‖∇d‖ = 1 in 2D by repeating the sweeps of
+/// [`fast_sweep_2d`](fn.fast_sweep_2d.html) until they converge.
+///
+/// The arguments are the same as for `fast_sweep_2d`. After every round of the four sweeps, the
+/// largest change of a value over the round is compared with `tol`, and the iteration ends when it
+/// is at most `tol`. With `tol = 0` the iteration ends when a round does not change anything.
+/// Usually, a single round suffices, but not when the characteristics wrap around obstacles.
+///
+/// Returns the number of sweeps performed, four per round, including the last round that
+/// confirmed the convergence. See [`fast_sweep_2d_config`](fn.fast_sweep_2d_config.html) for a
+/// cap on the number of rounds.
+pub fn fast_sweep_2d_iters<F>(d: &mut [f64], dim: (usize, usize), inv_norm: F, tol: f64) -> usize
+where
+    F: Fn(f64, [f64; 2], [f64; 2]) -> f64,
+{
+    sweep_rounds(d, dim, inv_norm, tol, std::usize::MAX).0
+}
+
+/// The stopping rule of [`fast_sweep_2d_config`](fn.fast_sweep_2d_config.html).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SweepConfig {
+    /// The iteration has converged when the largest change of a value over a round of the four
+    /// sweeps is at most `tol`.
+    pub tol: f64,
+    /// The maximal number of rounds.
+    pub max_iters: usize,
+}
+
+impl Default for SweepConfig {
+    /// Iterates until nothing changes, for at most 100 rounds.
+    fn default() -> SweepConfig {
+        SweepConfig {
+            tol: 0.,
+            max_iters: 100,
+        }
+    }
+}
+
+/// Computes the solution of the eikonal equation ‖∇d‖ = 1 in 2D like
+/// [`fast_sweep_2d_iters`](fn.fast_sweep_2d_iters.html), with at most `config.max_iters` rounds
+/// of the four sweeps.
+///
+/// Returns the number of sweeps performed, or `SweepError::NotConverged` if the last of the
+/// `max_iters` rounds still changed a value by more than `config.tol`. `d` contains the values
+/// after the last round in either case. The grid must be at least 3×3 and `d` must have one value
+/// per node, otherwise the error is returned before any sweep.
+pub fn fast_sweep_2d_config<F>(
+    d: &mut [f64],
+    dim: (usize, usize),
+    inv_norm: F,
+    config: SweepConfig,
+) -> Result<usize, SweepError>
+where
+    F: Fn(f64, [f64; 2], [f64; 2]) -> f64,
+{
+    Preflight::new(dim.0 * dim.1)
+        .min_dim(&[dim.0, dim.1], 3)?
+        .len("d", d.len())?;
+    let (sweeps, change) = sweep_rounds(d, dim, inv_norm, config.tol, config.max_iters);
+    if change <= config.tol {
+        Ok(sweeps)
+    } else {
+        Err(SweepError::NotConverged { sweeps, change })
+    }
+}
+
+/// Repeats the rounds of [`fast_sweep_2d`](fn.fast_sweep_2d.html) until the largest change over a
+/// round is at most `tol`, or `max_iters` rounds were performed. Returns the number of sweeps and
+/// the largest change over the last round.
+fn sweep_rounds<F>(
+    d: &mut [f64],
+    dim: (usize, usize),
+    inv_norm: F,
+    tol: f64,
+    max_iters: usize,
+) -> (usize, f64)
+where
+    F: Fn(f64, [f64; 2], [f64; 2]) -> f64,
+{
+    let mut prev = d.to_vec();
+    let mut sweeps = 0;
+    let mut change = std::f64::INFINITY;
+    for _ in 0..max_iters {
+        fast_sweep_2d(d, dim, &inv_norm);
+        sweeps += 4;
+        change = d
+            .iter()
+            .zip(&prev)
+            .fold(0., |m, (d, p)| max(m, (p - d).abs()));
+        if change <= tol {
+            break;
+        }
+        prev.copy_from_slice(d);
+    }
+    (sweeps, change)
+}
+
+/// A method of computing the Euclidean distance ‖∇d‖₂ = 1 in 2D in grid units, so that the
+/// callers like [`signed_distance_2d_with`](../fn.signed_distance_2d_with.html) can be run with
+/// different solvers on the same initialization.
+///
+/// `d` is initialized to the known distances near the interface and to `std::f64::MAX` elsewhere.
+pub trait EikonalSolver {
+    /// Computes the distance at the unknown nodes of `d` on a grid of dimensions `dim`.
+    fn solve(&self, d: &mut [f64], dim: (usize, usize));
+}
+
+/// One round of the sweeps in the four directions, [`fast_sweep_2d`](fn.fast_sweep_2d.html).
+#[derive(Clone, Copy, Debug)]
+pub struct FastSweeping;
+
+impl EikonalSolver for FastSweeping {
+    fn solve(&self, d: &mut [f64], dim: (usize, usize)) {
+        fast_sweep_2d(d, dim, |d, v, s| EuclideanNorm.inv_dual_norm(d, v, s));
+    }
+}
+
+/// The fast marching method, [`fast_march_dist_2d`](fn.fast_march_dist_2d.html).
+#[derive(Clone, Copy, Debug)]
+pub struct FastMarching;
+
+impl EikonalSolver for FastMarching {
+    fn solve(&self, d: &mut [f64], dim: (usize, usize)) {
+        fast_march_dist_2d(d, dim);
+    }
+}
+
+/// The fast iterative method, [`fast_iterative_dist_2d`](fn.fast_iterative_dist_2d.html).
+#[derive(Clone, Copy, Debug)]
+pub struct FastIterative;
+
+impl EikonalSolver for FastIterative {
+    fn solve(&self, d: &mut [f64], dim: (usize, usize)) {
+        fast_iterative_dist_2d(d, dim);
+    }
+}
+
+/// A node in the narrow band of the fast marching method, ordered so that the `BinaryHeap` pops
+/// the smallest distance first.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Trial {
+    d: f64,
+    s: usize,
+}
+
+impl Eq for Trial {}
+
+impl PartialOrd for Trial {
+    fn partial_cmp(&self, other: &Trial) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Trial {
+    fn cmp(&self, other: &Trial) -> cmp::Ordering {
+        other
+            .d
+            .partial_cmp(&self.d)
+            .unwrap_or(cmp::Ordering::Equal)
+            .then(other.s.cmp(&self.s))
+    }
+}
+
+/// Computes the Euclidean distance ‖∇d‖₂ = 1 in 2D in grid units using the fast marching method.
+///
+/// `d` should be initialized near the interface, for instance by
+/// [`init_dist_2d`](../level_set/fn.init_dist_2d.html), and to `std::f64::MAX` elsewhere. The
+/// initialized nodes are accepted as they are. The neighbors of the accepted nodes form the narrow
+/// band, kept in a binary heap; the node of the band with the smallest distance is accepted next
+/// and its neighbors are updated by the Godunov upwind update from the accepted nodes only. Every
+/// node is thus accepted once, in the order of increasing distance, in O(n log n) time.
+///
+/// The result is the solution of the same upwind discretization as the sweeps reach when repeated
+/// until nothing changes, except that the initialized nodes are never lowered. Unlike the sweeps,
+/// the number of operations does not depend on how often the characteristics change direction.
+pub fn fast_march_dist_2d(d: &mut [f64], dim: (usize, usize)) {
+    let (ni, nj) = dim;
+    assert_eq!(ni * nj, d.len());
+
+    let mut accepted: Vec<bool> = d.iter().map(|&d| d < std::f64::MAX).collect();
+    let mut band = BinaryHeap::new();
+    for s in 0..ni * nj {
+        if accepted[s] {
+            for &t in neighbors_2d(s, dim).iter().flatten() {
+                if !accepted[t] {
+                    march_update(d, &accepted, &mut band, t, dim);
+                }
+            }
+        }
+    }
+    while let Some(Trial { d: v, s }) = band.pop() {
+        // a node can be in the band several times with decreasing values
+        if accepted[s] || v > d[s] {
+            continue;
+        }
+        accepted[s] = true;
+        for &t in neighbors_2d(s, dim).iter().flatten() {
+            if !accepted[t] {
+                march_update(d, &accepted, &mut band, t, dim);
+            }
+        }
+    }
+}
+
+/// Computes the Euclidean distance ‖∇d‖₂ = 1 in 2D in grid units using the fast iterative method
+/// of Jeong and Whitaker (2008).
+///
+/// `d` should be initialized near the interface, for instance by
+/// [`init_dist_2d`](../level_set/fn.init_dist_2d.html), and to `std::f64::MAX` elsewhere. The
+/// initialized nodes are kept fixed. The active list starts with their neighbors. In every
+/// iteration each node of the list is updated by the Godunov upwind update from its four
+/// neighbors; a node whose value has not changed is removed from the list and its neighbors that
+/// its value lowers are added to it. The iterations end when the list is empty.
+///
+/// Only the nodes near the moving front are visited, and the nodes of the list can be updated
+/// independently of each other, which makes the method suitable for GPUs. The result is the
+/// solution of the same discretization as that of
+/// [`fast_march_dist_2d`](fn.fast_march_dist_2d.html). Returns the number of iterations.
+pub fn fast_iterative_dist_2d(d: &mut [f64], dim: (usize, usize)) -> usize {
+    let (ni, nj) = dim;
+    assert_eq!(ni * nj, d.len());
+
+    let fixed: Vec<bool> = d.iter().map(|&d| d < std::f64::MAX).collect();
+    let mut active = vec![false; ni * nj];
+    let mut list = vec![];
+    for s in 0..ni * nj {
+        if fixed[s] {
+            for &t in neighbors_2d(s, dim).iter().flatten() {
+                if !fixed[t] && !active[t] {
+                    active[t] = true;
+                    list.push(t);
+                }
+            }
+        }
+    }
+
+    let mut iters = 0;
+    while !list.is_empty() {
+        iters += 1;
+        let mut next = vec![];
+        for &s in &list {
+            let v = fim_update(d, s, dim);
+            if v < d[s] {
+                d[s] = v;
+                next.push(s);
+                continue;
+            }
+            // converged: wake up the neighbors
+            active[s] = false;
+            for &t in neighbors_2d(s, dim).iter().flatten() {
+                if fixed[t] || active[t] {
+                    continue;
+                }
+                let v = fim_update(d, t, dim);
+                if v < d[t] {
+                    d[t] = v;
+                    active[t] = true;
+                    next.push(t);
+                }
+            }
+        }
+        list = next;
+    }
+    iters
+}
+
+/// The Godunov upwind update of the node `s` from its four neighbors.
+fn fim_update(d: &[f64], s: usize, dim: (usize, usize)) -> f64 {
+    let n = neighbors_2d(s, dim);
+    let value = |t: Option<usize>| t.map_or(std::f64::MAX, |t| d[t]);
+    let a = min(value(n[0]), value(n[1]));
+    let b = min(value(n[2]), value(n[3]));
+    EuclideanNorm.inv_dual_norm(d[s], [a, b], [1., 1.])
+}
+
+/// The indices of the four neighbors of the node `s` that are inside the grid.
+fn neighbors_2d(s: usize, dim: (usize, usize)) -> [Option<usize>; 4] {
+    let (ni, nj) = dim;
+    let (i, j) = (s / nj, s % nj);
+    [
+        if i > 0 { Some(s - nj) } else { None },
+        if i + 1 < ni { Some(s + nj) } else { None },
+        if j > 0 { Some(s - 1) } else { None },
+        if j + 1 < nj { Some(s + 1) } else { None },
+    ]
+}
+
+/// Updates the node `s` in the band of [`fast_march_dist_2d`](fn.fast_march_dist_2d.html) from
+/// its accepted neighbors.
+fn march_update(
+    d: &mut [f64],
+    accepted: &[bool],
+    band: &mut BinaryHeap<Trial>,
+    s: usize,
+    dim: (usize, usize),
+) {
+    let n = neighbors_2d(s, dim);
+    let value = |t: Option<usize>| match t {
+        Some(t) if accepted[t] => d[t],
+        _ => std::f64::MAX,
+    };
+    let a = min(value(n[0]), value(n[1]));
+    let b = min(value(n[2]), value(n[3]));
+    let v = EuclideanNorm.inv_dual_norm(d[s], [a, b], [1., 1.]);
+    if v < d[s] {
+        d[s] = v;
+        band.push(Trial { d: v, s });
+    }
+}
+
 /// Computes the solution of the eikonal equation ‖∇d‖ = 1 in 3D using the fast sweeping algorithm.
 ///
 /// `d` should be initialized to a large value at the unknown nodes.
@@ -232,6 +954,273 @@ where
     }
 }
 
+/// Computes the Euclidean distance ‖∇d‖₂ = 1 in 3D in grid units using
+/// [`fast_sweep_3d`](fn.fast_sweep_3d.html).
+///
+/// `d` should be initialized to a large value at the unknown nodes. The update at a node is the
+/// Godunov upwind update: the quadratic equation is formed by the smaller neighbor along each
+/// axis, and the largest neighbors are dropped while they are not smaller than the solution.
+pub fn fast_sweep_dist_3d(d: &mut [f64], dim: (usize, usize, usize)) {
+    fast_sweep_3d(d, dim, |d, v, s| EuclideanNorm.inv_dual_norm(d, v, s));
+}
+
+/// Computes the Euclidean distance ‖∇d‖₂ = 1 in 2D in grid units in the free space between
+/// obstacles, so that the distance goes around the obstacles instead of through them.
+///
+/// `d` should be initialized to `0` at the sources and to `std::f64::MAX` elsewhere. The nodes
+/// marked in `blocked` are never updated and never used as upwind neighbors. The free nodes that
+/// cannot be reached from any source, for instance a node surrounded by blocked nodes, keep their
+/// initial value.
+///
+/// The sweeps in the four directions are repeated until nothing changes, since the free space
+/// need not be convex.
+pub fn fast_sweep_dist_2d_masked(d: &mut [f64], blocked: &[bool], dim: (usize, usize)) {
+    let (ni, nj) = dim;
+    assert_eq!(ni * nj, d.len());
+    assert_eq!(ni * nj, blocked.len());
+
+    sweep_2d_repeat(
+        d,
+        dim,
+        0.,
+        |s| blocked[s],
+        |d, s, ij, dir| {
+            // the blocked nodes count as MAX
+            let v = upwind_2d(d, dim, s, ij, dir, |t| blocked[t]);
+            EuclideanNorm.inv_dual_norm(d[s], v, [dir.0 as f64, dir.1 as f64])
+        },
+    );
+}
+
+/// Computes the Euclidean distance ‖∇d‖₂ = 1 in 2D in grid units, never changing the nodes marked
+/// in `frozen`.
+///
+/// The frozen nodes are the sources: typically the nodes initialized near the interface by
+/// [`init_dist_2d`](../level_set/fn.init_dist_2d.html), that is, those with a value below
+/// `std::f64::MAX` after it, whose distances come from the crossing of the level set. They are
+/// used as upwind neighbors but keep their values, so the sweeps cannot move the interface. The
+/// other nodes should be `std::f64::MAX`.
+///
+/// The sweeps in the four directions are repeated until nothing changes.
+pub fn fast_sweep_dist_2d_frozen(d: &mut [f64], frozen: &[bool], dim: (usize, usize)) {
+    let (ni, nj) = dim;
+    assert_eq!(ni * nj, d.len());
+    assert_eq!(ni * nj, frozen.len());
+
+    let inv_norm = |d: f64, v: [f64; 2], s: [f64; 2]| EuclideanNorm.inv_dual_norm(d, v, s);
+    sweep_2d_repeat(
+        d,
+        dim,
+        0.,
+        |s| frozen[s],
+        |d, s, ij, dir| norm_update(&inv_norm, d, dim, s, ij, dir),
+    );
+}
+
+/// Computes the Euclidean distance ‖∇d‖₂ = 1 in 2D in grid units with a second order upwind
+/// discretization.
+///
+/// `d` should be initialized near the interface, for instance by
+/// [`init_dist_2d`](../level_set/fn.init_dist_2d.html), and to `std::f64::MAX` elsewhere. The
+/// initialized nodes are kept fixed. The first order solution is computed first and then improved
+/// by the sweeps with the second order one-sided differences `(3 d₀ - 4 d₁ + d₂) / 2`, following
+/// Zhang, Zhao and Qian (2006). Along an axis the second order difference is used only if both
+/// upwind neighbors are inside the grid, the nearer one is not an initialized node (so that the
+/// stencil does not reach across the interface) and they decrease towards the interface;
+/// otherwise the update falls back to the first order one.
+///
+/// Away from the interface and the kinks of the distance the error is close to O(h²) instead of
+/// O(h log h). The sweeps are repeated until the largest change is below `1e-12`.
+pub fn fast_sweep_dist_2d_ho(d: &mut [f64], dim: (usize, usize)) {
+    assert_eq!(dim.0 * dim.1, d.len());
+    let fixed: Vec<bool> = d.iter().map(|&d| d < std::f64::MAX).collect();
+    sweep_dist_2d_order(d, &fixed, dim, false);
+    sweep_dist_2d_order(d, &fixed, dim, true);
+}
+
+/// The sweeps of [`fast_sweep_dist_2d_ho`](fn.fast_sweep_dist_2d_ho.html) with the first order
+/// update, or with the second order one where possible if `second`.
+fn sweep_dist_2d_order(d: &mut [f64], fixed: &[bool], dim: (usize, usize), second: bool) {
+    let (ni, nj) = dim;
+    // the upwind value along an axis and the coefficient of the difference, given the position
+    // `p` of the node along the axis of length `n` with the index step `stride`
+    let upwind = |d: &[f64], s: usize, p: usize, n: usize, stride: usize, dir: isize| {
+        let (s1, room) = if dir == 1 {
+            if p == 0 {
+                return (std::f64::MAX, 1.);
+            }
+            (s - stride, p >= 2)
+        } else {
+            if p + 1 == n {
+                return (std::f64::MAX, 1.);
+            }
+            (s + stride, p + 2 < n)
+        };
+        let a1 = d[s1];
+        if second && room && !fixed[s1] && a1 < std::f64::MAX {
+            let a2 = if dir == 1 {
+                d[s1 - stride]
+            } else {
+                d[s1 + stride]
+            };
+            if a2 <= a1 {
+                return ((4. * a1 - a2) / 3., 1.5);
+            }
+        }
+        (a1, 1.)
+    };
+
+    sweep_2d_repeat(
+        d,
+        dim,
+        1e-12,
+        |s| fixed[s],
+        |d, s, (i, j), (si, sj)| {
+            solve_upwind(upwind(d, s, i, ni, nj, si), upwind(d, s, j, nj, 1, sj))
+        },
+    );
+}
+
+/// The largest solution `t` of `Σ cₖ² ((t - mₖ)₊)² = 1` for the upwind values `mₖ` and the
+/// coefficients `cₖ` of the two axes.
+fn solve_upwind(a: (f64, f64), b: (f64, f64)) -> f64 {
+    let ((m1, c1), (m2, c2)) = (a, b);
+    if m1 < std::f64::MAX && m2 < std::f64::MAX {
+        let (c1, c2) = (c1 * c1, c2 * c2);
+        let qa = c1 + c2;
+        let qb = c1 * m1 + c2 * m2;
+        let qc = c1 * m1 * m1 + c2 * m2 * m2 - 1.;
+        let disc = qb * qb - qa * qc;
+        if disc >= 0. {
+            let t = (qb + disc.sqrt()) / qa;
+            if t >= max(m1, m2) {
+                return t;
+            }
+        }
+    }
+    // only one axis; MAX stays MAX
+    min(m1 + 1. / c1, m2 + 1. / c2)
+}
+
+/// Computes the travel times `t` of a front moving with the speed `speed` on a regular 2D grid
+/// with spacing `h`, that is, the solution of the eikonal equation `|∇t| = 1 / speed`.
+///
+/// `t` should be initialized to the known times at the sources (typically `0`) and to
+/// `std::f64::MAX` elsewhere. The Godunov upwind update at a node uses the local slowness
+/// `1 / speed`. The nodes with zero speed are impassable: the front goes around them and they are
+/// left at `std::f64::MAX`, as are the nodes that the front cannot reach.
+///
+/// The sweeps in the four directions are repeated until the times do not change anymore. With a
+/// smooth speed a few rounds suffice, but every sharp change of the speed that bends the
+/// characteristics back against the sweep directions costs an extra round. Across a jump of the
+/// speed the times stay continuous, but the first order scheme smears the refraction over a few
+/// cells, so the error near the jump is larger than in the smooth regions.
+pub fn fast_sweep_time(t: &mut [f64], speed: &[f64], dim: (usize, usize), h: f64) {
+    let (ni, nj) = dim;
+    assert_eq!(ni * nj, t.len());
+    assert_eq!(ni * nj, speed.len());
+    for &c in speed {
+        assert!(
+            c >= 0. && c.is_finite(),
+            "The speed must be nonnegative and finite, was {}",
+            c
+        );
+    }
+
+    for (t, &c) in t.iter_mut().zip(speed) {
+        if c == 0. {
+            *t = std::f64::MAX;
+        }
+    }
+
+    sweep_2d_repeat(
+        t,
+        dim,
+        0.,
+        |s| speed[s] == 0.,
+        |t, s, (i, j), _| {
+            // the smaller neighbor along each axis
+            let mut a = std::f64::MAX;
+            if i > 0 {
+                a = min(a, t[s - nj]);
+            }
+            if i + 1 < ni {
+                a = min(a, t[s + nj]);
+            }
+            let mut b = std::f64::MAX;
+            if j > 0 {
+                b = min(b, t[s - 1]);
+            }
+            if j + 1 < nj {
+                b = min(b, t[s + 1]);
+            }
+            if a == std::f64::MAX && b == std::f64::MAX {
+                return std::f64::MAX;
+            }
+            let f = h / speed[s];
+            if (a - b).abs() >= f {
+                min(a, b) + f
+            } else {
+                0.5 * (a + b + (2. * f * f - (a - b) * (a - b)).sqrt())
+            }
+        },
+    );
+}
+
+/// Computes the travel times `t` in an anisotropic medium on a regular 2D grid with spacing `h`,
+/// that is, the solution of the eikonal equation `√(∇tᵀ M ∇t) = 1` with the symmetric positive
+/// definite matrix `M` varying from node to node.
+///
+/// `metric` gives `M` at every node as `[m11, m12, m22]`; for an isotropic medium with the speed
+/// `c` it is `[c², 0, c²]`. `t` should be initialized to the known times at the sources and to
+/// `std::f64::MAX` elsewhere, like for [`fast_sweep_time`](fn.fast_sweep_time.html).
+///
+/// The time to travel the displacement `w` near a node is `√(wᵀ M⁻¹ w)`, which is the norm of
+/// [`AffineNorm::from_metric`](../norm/struct.AffineNorm.html#method.from_metric) with the metric
+/// `h² M⁻¹`. The update of a node is the semi-Lagrangian update of that norm, the minimum over the
+/// four quadrants of the paths to the segment between the two upwind neighbors, so the
+/// characteristics do not need to be aligned with the gradient. With `M = c² I` the result is
+/// that of `fast_sweep_time`.
+///
+/// The sweeps in the four directions are repeated until nothing changes. Panics if `M` is not
+/// positive definite at some node.
+pub fn fast_sweep_aniso(t: &mut [f64], metric: &[[f64; 3]], dim: (usize, usize), h: f64) {
+    let (ni, nj) = dim;
+    assert_eq!(ni * nj, t.len());
+    assert_eq!(ni * nj, metric.len());
+
+    let norms: Vec<AffineNorm> = metric
+        .iter()
+        .map(|&[m11, m12, m22]| {
+            let det = m11 * m22 - m12 * m12;
+            assert!(
+                m11 > 0. && det > 0.,
+                "The metric must be positive definite, got {:?}",
+                [m11, m12, m22]
+            );
+            let k = h * h / det;
+            AffineNorm::from_metric([[k * m22, -k * m12], [-k * m12, k * m11]])
+        })
+        .collect();
+
+    sweep_2d_repeat(
+        t,
+        dim,
+        0.,
+        |_| false,
+        |t, s, ij, _| {
+            let mut x = t[s];
+            for &(qi, qj) in &SWEEP_DIRS_2D {
+                let [vi, vj] = upwind_2d(t, dim, s, ij, (qi, qj), |_| false);
+                if vi < std::f64::MAX || vj < std::f64::MAX {
+                    x = norms[s].inv_dual_norm(x, [vi, vj], [qi as f64, qj as f64]);
+                }
+            }
+            x
+        },
+    );
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -330,6 +1319,146 @@ mod tests {
         }
     }
 
+    #[test]
+    fn stopping_predicate() {
+        use norm::{DualNorm, EuclideanNorm};
+        let n = 21;
+        let inv_norm = |d, v, s| EuclideanNorm.inv_dual_norm(d, v, s);
+        let seeded = || {
+            let mut d = vec![std::f64::MAX; n * n];
+            d[18 * n + 18] = 0.;
+            d
+        };
+        let max_reached = |d: &[f64]| {
+            d.iter()
+                .cloned()
+                .filter(|&d| d < std::f64::MAX)
+                .fold(0., f64::max)
+        };
+
+        // the first sweep reaches only the nodes i, j >= 18, the second one the rest
+        let mut d = seeded();
+        let mut calls = vec![];
+        let sweeps = fast_sweep_2d_until(&mut d, (n, n), inv_norm, |d, k| {
+            calls.push(k);
+            max_reached(d) > 10.
+        });
+        assert_eq!(sweeps, 2);
+        assert_eq!(calls, [0, 1]);
+        assert!(max_reached(&d) > 25.);
+
+        // without stopping, the iteration ends after four sweeps without any change
+        let mut d = seeded();
+        let sweeps = fast_sweep_2d_until(&mut d, (n, n), inv_norm, |_, _| false);
+        let mut e = seeded();
+        fast_sweep_2d(&mut e, (n, n), inv_norm);
+        assert_eq!(sweeps, 8);
+        for (d, e) in d.iter().zip(&e) {
+            assert!((d - e).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn iterations_around_wall() {
+        let n = 21;
+        // the nodes marked by a negative value are a wall that the information does not cross
+        let inv_norm = |d: f64, v: [f64; 2], s: [f64; 2]| {
+            if d < 0. {
+                return d;
+            }
+            let open = |v: f64| if v < 0. { std::f64::MAX } else { v };
+            EuclideanNorm.inv_dual_norm(d, [open(v[0]), open(v[1])], s)
+        };
+
+        // a point source converges in one round
+        let mut d = vec![std::f64::MAX; n * n];
+        d[18 * n + 5] = 0.;
+        let mut e = d.clone();
+        assert_eq!(fast_sweep_2d_iters(&mut d, (n, n), inv_norm, 0.), 8);
+        fast_sweep_2d(&mut e, (n, n), inv_norm);
+        assert_eq!(d, e);
+
+        // around the wall along i = 10, the characteristics turn back
+        for s in 0..n * n {
+            d[s] = if s / n == 10 && s % n < 17 {
+                -1.
+            } else {
+                std::f64::MAX
+            };
+        }
+        d[15 * n + 2] = 0.;
+        let sweeps = fast_sweep_2d_iters(&mut d, (n, n), inv_norm, 0.);
+        assert!(sweeps > 8 && sweeps % 4 == 0, "{}", sweeps);
+        // another round of the sweeps changes nothing
+        let mut e = d.clone();
+        fast_sweep_2d(&mut e, (n, n), inv_norm);
+        assert_eq!(d, e);
+        // the node behind the wall is reached around its end
+        assert!(d[5 * n + 2] > 20.);
+    }
+
+    #[test]
+    fn config_reports_no_convergence() {
+        let n = 21;
+        let inv_norm = |d: f64, v: [f64; 2], s: [f64; 2]| {
+            if d < 0. {
+                return d;
+            }
+            let open = |v: f64| if v < 0. { std::f64::MAX } else { v };
+            EuclideanNorm.inv_dual_norm(d, [open(v[0]), open(v[1])], s)
+        };
+        let walled = || {
+            let mut d = vec![std::f64::MAX; n * n];
+            for j in 0..17 {
+                d[10 * n + j] = -1.;
+            }
+            d[15 * n + 2] = 0.;
+            d
+        };
+        let mut d = walled();
+        let sweeps = fast_sweep_2d_iters(&mut d, (n, n), inv_norm, 0.);
+
+        let mut e = walled();
+        let config = SweepConfig {
+            max_iters: sweeps / 4,
+            ..SweepConfig::default()
+        };
+        assert_eq!(fast_sweep_2d_config(&mut e, (n, n), inv_norm, config), Ok(sweeps));
+        assert_eq!(d, e);
+
+        // without the round that confirms the convergence
+        let mut e = walled();
+        let config = SweepConfig {
+            max_iters: sweeps / 4 - 1,
+            ..SweepConfig::default()
+        };
+        match fast_sweep_2d_config(&mut e, (n, n), inv_norm, config) {
+            Err(SweepError::NotConverged { sweeps: k, change }) => {
+                assert_eq!(k, sweeps - 4);
+                assert!(change > 0.);
+            }
+            r => panic!("{:?}", r),
+        }
+
+        let config = SweepConfig::default();
+        assert_eq!(
+            fast_sweep_2d_config(&mut e[..n * n - 1], (n, n), inv_norm, config),
+            Err(SweepError::LengthMismatch {
+                name: "d",
+                expected: n * n,
+                found: n * n - 1
+            })
+        );
+        assert_eq!(
+            fast_sweep_2d_config(&mut e[..2 * n], (2, n), inv_norm, config),
+            Err(SweepError::GridTooSmall {
+                axis: 0,
+                len: 2,
+                min: 3
+            })
+        );
+    }
+
     /// Check that i and j components are passed correctly .
     #[test]
     fn fast_sweep_2d_directionality() {
@@ -343,4 +1472,385 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn fast_sweep_dist_3d_plane() {
+        let (ni, nj, nk) = (13, 11, 16);
+        // unit normal
+        let n = [2. / 7., -3. / 7., 6. / 7.];
+        let exact = |i: usize, j: usize, k: usize| {
+            (n[0] * i as f64 + n[1] * j as f64 + n[2] * k as f64 - 5.3).abs()
+        };
+        let mut d = vec![std::f64::MAX; ni * nj * nk];
+        for i in 0..ni {
+            for j in 0..nj {
+                for k in 0..nk {
+                    let e = exact(i, j, k);
+                    // exact values near the plane and on the boundary
+                    if e <= 1.
+                        || i == 0
+                        || j == 0
+                        || k == 0
+                        || i == ni - 1
+                        || j == nj - 1
+                        || k == nk - 1
+                    {
+                        d[(i * nj + j) * nk + k] = e;
+                    }
+                }
+            }
+        }
+        fast_sweep_dist_3d(&mut d, (ni, nj, nk));
+        for i in 0..ni {
+            for j in 0..nj {
+                for k in 0..nk {
+                    let (v, e) = (d[(i * nj + j) * nk + k], exact(i, j, k));
+                    assert!((v - e).abs() < 1e-9, "{:?}: {} != {}", (i, j, k), v, e);
+                }
+            }
+        }
+
+        // only the band near the plane: the values in the interior are still exact when the
+        // upwind neighbors are inside the grid, everywhere else they are upper bounds
+        let mut d = vec![std::f64::MAX; ni * nj * nk];
+        for i in 0..ni {
+            for j in 0..nj {
+                for k in 0..nk {
+                    if exact(i, j, k) <= 1. {
+                        d[(i * nj + j) * nk + k] = exact(i, j, k);
+                    }
+                }
+            }
+        }
+        fast_sweep_dist_3d(&mut d, (ni, nj, nk));
+        for i in 0..ni {
+            for j in 0..nj {
+                for k in 0..nk {
+                    assert!(d[(i * nj + j) * nk + k] >= exact(i, j, k) - 1e-9);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn travel_time_speed_and_wall() {
+        let n = 31;
+        let h = 0.1;
+        let source = |n: usize| {
+            let mut t = vec![std::f64::MAX; n * n];
+            t[15 * n + 15] = 0.;
+            t
+        };
+
+        // the times scale with the slowness
+        let mut slow = source(n);
+        fast_sweep_time(&mut slow, &vec![1.; n * n], (n, n), h);
+        let mut fast = source(n);
+        fast_sweep_time(&mut fast, &vec![2.; n * n], (n, n), h);
+        for s in 0..n * n {
+            assert!((fast[s] - 0.5 * slow[s]).abs() < 1e-12, "{} != {}", fast[s], slow[s]);
+        }
+        assert!((slow[15 * n + 25] - 1.).abs() < 1e-12);
+
+        // a wall with a gap at the top
+        let mut speed = vec![1.; n * n];
+        for i in 5..n {
+            speed[i * n + 20] = 0.;
+        }
+        let mut t = source(n);
+        fast_sweep_time(&mut t, &speed, (n, n), h);
+        for i in 5..n {
+            assert_eq!(t[i * n + 20], std::f64::MAX);
+        }
+        // behind the wall the front has to go around it through the gap
+        let s = 15 * n + 25;
+        assert!(t[s] < std::f64::MAX);
+        assert!(t[s] > 1.9, "{}", t[s]);
+        assert!(t[15 * n + 10] == slow[15 * n + 10]);
+    }
+
+    #[test]
+    fn anisotropic_travel_times() {
+        let n = 21;
+        let h = 0.05;
+
+        // isotropic with the speed 2
+        let mut iso = vec![std::f64::MAX; n * n];
+        iso[10 * n + 10] = 0.;
+        let mut t = iso.clone();
+        fast_sweep_time(&mut iso, &vec![2.; n * n], (n, n), h);
+        fast_sweep_aniso(&mut t, &vec![[4., 0., 4.]; n * n], (n, n), h);
+        for s in 0..n * n {
+            assert!((t[s] - iso[s]).abs() < 1e-12, "{} != {}", t[s], iso[s]);
+        }
+
+        // the speeds 1 and 1/2 along the axes rotated by 30°, a plane wave entering at the edges
+        // i = 0 and j = 0 is exact
+        let (c, s) = (0.5 * 3f64.sqrt(), 0.5);
+        let m = [c * c + 0.25 * s * s, 0.75 * c * s, s * s + 0.25 * c * c];
+        let q = [1., 0.7];
+        let len = (m[0] * q[0] * q[0] + 2. * m[1] * q[0] * q[1] + m[2] * q[1] * q[1]).sqrt();
+        let plane = |i: usize, j: usize| (q[0] * i as f64 + q[1] * j as f64) * h / len;
+        let mut t = vec![std::f64::MAX; n * n];
+        for k in 0..n {
+            t[k] = plane(0, k);
+            t[k * n] = plane(k, 0);
+        }
+        fast_sweep_aniso(&mut t, &vec![m; n * n], (n, n), h);
+        for i in 0..n {
+            for j in 0..n {
+                let (v, e) = (t[i * n + j], plane(i, j));
+                assert!((v - e).abs() < 1e-12, "{} != {}", v, e);
+            }
+        }
+    }
+
+    #[test]
+    fn frozen_nodes_keep_their_values() {
+        let n = 9;
+        // a source and a frozen neighbor with a wrong value
+        let sweep = |wrong: f64| {
+            let mut d = vec![std::f64::MAX; n * n];
+            let mut frozen = vec![false; n * n];
+            for &(s, v) in &[(4 * n + 4, 0.), (4 * n + 5, wrong)] {
+                d[s] = v;
+                frozen[s] = true;
+            }
+            fast_sweep_dist_2d_frozen(&mut d, &frozen, (n, n));
+            assert!(d.iter().all(|&d| d < std::f64::MAX));
+            d
+        };
+        // too large: kept instead of lowered to 1
+        let d = sweep(3.);
+        assert_eq!(d[4 * n + 5], 3.);
+        assert_eq!(d[4 * n + 4], 0.);
+        assert_eq!(d[4 * n + 3], 1.);
+        // too small: kept and used as a source
+        let d = sweep(0.25);
+        assert_eq!(d[4 * n + 5], 0.25);
+        assert_eq!(d[4 * n + 6], 1.25);
+    }
+
+    #[test]
+    fn sweeps_in_a_given_order() {
+        let (ni, nj) = (9, 12);
+        let source = 3 * nj + 7;
+        let init = || {
+            let mut d = vec![std::f64::MAX; ni * nj];
+            d[source] = 0.;
+            d
+        };
+
+        // a point source needs a single round of the four sweeps
+        let mut d = init();
+        assert_eq!(
+            fast_sweep_dist_with_order(&mut d, (ni, nj), &SweepDir::ALL),
+            8
+        );
+        let mut expected = init();
+        let mut frozen = vec![false; ni * nj];
+        frozen[source] = true;
+        fast_sweep_dist_2d_frozen(&mut expected, &frozen, (ni, nj));
+        assert_eq!(d, expected);
+
+        // a single direction reaches only its quadrant
+        let mut d = init();
+        assert_eq!(
+            fast_sweep_dist_with_order(&mut d, (ni, nj), &[SweepDir::PlusPlus]),
+            2
+        );
+        for i in 0..ni {
+            for j in 0..nj {
+                let s = i * nj + j;
+                if i >= 3 && j >= 7 {
+                    assert_eq!(d[s], expected[s]);
+                } else {
+                    assert_eq!(d[s], std::f64::MAX);
+                }
+            }
+        }
+
+        // the order of the directions does not matter for the converged result
+        let mut d = init();
+        let order = [SweepDir::MinusPlus, SweepDir::PlusMinus, SweepDir::PlusPlus];
+        fast_sweep_dist_with_order(&mut d, (ni, nj), &order);
+        fast_sweep_dist_with_order(&mut d, (ni, nj), &[SweepDir::MinusMinus]);
+        assert_eq!(d, expected);
+    }
+
+    #[test]
+    fn progress_reports_the_residuals() {
+        let n = 10;
+        let inv_norm = |d: f64, v: [f64; 2], s: [f64; 2]| EuclideanNorm.inv_dual_norm(d, v, s);
+        let mut expected = vec![std::f64::MAX; n * n];
+        expected[4 * n + 5] = 0.;
+        let mut d = expected.clone();
+        fast_sweep_2d_until(&mut expected, (n, n), inv_norm, |_, _| false);
+
+        let mut log = vec![];
+        let sweeps = fast_sweep_2d_progress(&mut d, (n, n), inv_norm, |k, r| log.push((k, r)));
+        assert_eq!(d, expected);
+        assert_eq!(sweeps, log.len());
+        assert_eq!(sweeps, 8);
+        for (k, &(i, r)) in log.iter().enumerate() {
+            assert_eq!(i, k);
+            // the first two sweeps reach all nodes, the next two improve the opposite quadrants
+            match k {
+                0 | 1 => assert_eq!(r, std::f64::INFINITY),
+                2 | 3 => assert!(r > 1. && r < 3., "{}", r),
+                _ => assert_eq!(r, 0.),
+            }
+        }
+
+        // finite initial values give finite residuals
+        let mut d = vec![100.; n * n];
+        d[4 * n + 5] = 0.;
+        let mut log = vec![];
+        fast_sweep_2d_progress(&mut d, (n, n), inv_norm, |_, r| log.push(r));
+        assert_eq!(d, expected);
+        assert_eq!(log[0], 99.);
+        assert!(log.iter().all(|r| r.is_finite()));
+    }
+
+    #[test]
+    fn fast_marching_and_iterative_match_sweeps() {
+        use level_set::init_dist_2d;
+        let (ni, nj) = (31, 26);
+        let h = 1. / 30.;
+        let mut u = vec![0.; ni * nj];
+        for i in 0..ni {
+            for j in 0..nj {
+                let (x, y) = (i as f64 * h - 0.45, j as f64 * h - 0.4);
+                // two circles, so that the characteristics meet
+                u[i * nj + j] = min(x.hypot(y) - 0.25, (x - 0.35).hypot(y - 0.3) - 0.1);
+            }
+        }
+        let mut init = vec![0.; ni * nj];
+        init_dist_2d(&mut init, &u, (ni, nj), |p| EuclideanNorm.dual_norm(p));
+
+        let mut e = init.clone();
+        fast_march_dist_2d(&mut e, (ni, nj));
+        // the same discretization with the initialized nodes fixed
+        let mut d = init.clone();
+        let frozen: Vec<bool> = init.iter().map(|&d| d < std::f64::MAX).collect();
+        fast_sweep_dist_2d_frozen(&mut d, &frozen, (ni, nj));
+        // the sweeps may lower a few of the initialized nodes
+        let mut f = init.clone();
+        fast_sweep_2d_iters(
+            &mut f,
+            (ni, nj),
+            |d, v, s| EuclideanNorm.inv_dual_norm(d, v, s),
+            0.,
+        );
+        for s in 0..ni * nj {
+            assert!(e[s] < std::f64::MAX);
+            assert!((d[s] - e[s]).abs() < 1e-12, "{} != {}", d[s], e[s]);
+            assert!((f[s] - e[s]).abs() < 0.1, "{} != {}", f[s], e[s]);
+        }
+
+        let mut g = init.clone();
+        assert!(fast_iterative_dist_2d(&mut g, (ni, nj)) > 1);
+        for s in 0..ni * nj {
+            assert!((g[s] - e[s]).abs() < 1e-12, "{} != {}", g[s], e[s]);
+        }
+
+        // no interface
+        let mut e = vec![std::f64::MAX; ni * nj];
+        FastMarching.solve(&mut e, (ni, nj));
+        assert!(e.iter().all(|&e| e == std::f64::MAX));
+        assert_eq!(fast_iterative_dist_2d(&mut e, (ni, nj)), 0);
+    }
+
+    #[test]
+    fn second_order_circle() {
+        // the largest error outside of a circle, away from the interface, of the first and the
+        // second order schemes
+        let errors = |n: usize| {
+            let h = 1. / (n - 1) as f64;
+            let r = |s: usize| ((s / n) as f64 * h - 0.5).hypot((s % n) as f64 * h - 0.5) - 0.25;
+            let init: Vec<f64> = (0..n * n)
+                .map(|s| {
+                    if r(s).abs() < h {
+                        r(s).abs() / h
+                    } else {
+                        std::f64::MAX
+                    }
+                })
+                .collect();
+            let fixed: Vec<bool> = init.iter().map(|&d| d < std::f64::MAX).collect();
+            let mut first = init.clone();
+            sweep_dist_2d_order(&mut first, &fixed, (n, n), false);
+            let mut second = init.clone();
+            fast_sweep_dist_2d_ho(&mut second, (n, n));
+            let mut e = (0f64, 0f64);
+            for s in 0..n * n {
+                if r(s) > 3. * h {
+                    e.0 = e.0.max((first[s] * h - r(s)).abs());
+                    e.1 = e.1.max((second[s] * h - r(s)).abs());
+                }
+            }
+            e
+        };
+        let coarse = errors(21);
+        let fine = errors(41);
+        assert!(coarse.1 < 0.2 * coarse.0, "{:?}", coarse);
+        assert!(fine.1 < 0.1 * fine.0, "{:?}", fine);
+        // the error of the first order scheme roughly halves, of the second order one more
+        assert!(fine.0 > coarse.0 / 2.5, "{:?} {:?}", coarse, fine);
+        assert!(fine.1 < coarse.1 / 2.5, "{:?} {:?}", coarse, fine);
+    }
+
+    #[test]
+    fn masked_distance_goes_around_wall() {
+        let n = 21;
+        let source = || {
+            let mut d = vec![std::f64::MAX; n * n];
+            d[10 * n + 5] = 0.;
+            d
+        };
+        // a wall in column 10 with a gap at the top, and a node boxed in at (15, 15)
+        let mut blocked = vec![false; n * n];
+        for i in 3..n {
+            blocked[i * n + 10] = true;
+        }
+        for &s in &[14 * n + 15, 16 * n + 15, 15 * n + 14, 15 * n + 16] {
+            blocked[s] = true;
+        }
+
+        let mut free = source();
+        fast_sweep_dist_2d_masked(&mut free, &vec![false; n * n], (n, n));
+        let mut d = source();
+        fast_sweep_dist_2d_masked(&mut d, &blocked, (n, n));
+
+        assert_eq!(d[10 * n + 0], free[10 * n + 0]);
+        assert_eq!(d[10 * n + 10], std::f64::MAX);
+        assert_eq!(d[15 * n + 15], std::f64::MAX);
+        // around the top of the wall, which ends between the rows 2 and 3
+        let around = 2. * 7f64.hypot(5.);
+        assert!(d[10 * n + 15] > around, "{} <= {}", d[10 * n + 15], around);
+        assert!(d[10 * n + 15] < 1.3 * around);
+        assert!((free[10 * n + 15] - 10.).abs() < 1e-12);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn parallel_sweep_is_identical() {
+        use level_set::init_dist_2d;
+        let (ni, nj) = (67, 45);
+        let h = 1. / 40.;
+        let mut u = vec![0.; ni * nj];
+        for i in 0..ni {
+            for j in 0..nj {
+                let (x, y) = (i as f64 * h - 0.7, j as f64 * h - 0.5);
+                u[i * nj + j] = x.hypot(y).min((x - 0.8).hypot(y - 0.3)) - 0.25;
+            }
+        }
+        let mut d = vec![0.; ni * nj];
+        init_dist_2d(&mut d, &u, (ni, nj), |p| EuclideanNorm.dual_norm(p));
+        let mut par = d.clone();
+        let inv_norm = |d: f64, v: [f64; 2], s: [f64; 2]| EuclideanNorm.inv_dual_norm(d, v, s);
+        fast_sweep_2d(&mut d, (ni, nj), inv_norm);
+        fast_sweep_2d_par(&mut par, (ni, nj), inv_norm);
+        assert_eq!(d, par);
+    }
 }
@@ -0,0 +1,206 @@
+//! Conversions of distance functions to compact representations.
+//!
+//! Each distance can be stored in 16 bits, either as a half precision float (behind the `half`
+//! feature) or as a fixed-point integer, or in 8 bits as a byte map. The inside/outside
+//! classification alone can be stored run-length encoded.
+#[cfg(feature = "half")]
+use half::f16;
+use signed_distance_2d;
+
+/// Converts the distance function to half precision floats, for instance for the upload to a GPU
+/// texture.
+///
+/// The values are clamped to the finite range of `f16`. In particular, the "no interface"
+/// sentinels `±std::f64::MAX` become `±f16::MAX` instead of infinity.
+///
+/// Requires the `half` feature.
+#[cfg(feature = "half")]
+pub fn to_f16(d: &[f64]) -> Vec<f16> {
+    to_f16_clamped(d, f16::MAX.to_f64())
+}
+
+/// Converts the distance function to half precision floats with the values clamped to the range
+/// `[-max, max]`.
+///
+/// `max` itself is clamped to `f16::MAX`, so the result never contains infinities (unless `max`
+/// is `NaN`). `NaN` values are preserved.
+///
+/// Requires the `half` feature.
+#[cfg(feature = "half")]
+pub fn to_f16_clamped(d: &[f64], max: f64) -> Vec<f16> {
+    let max = max.min(f16::MAX.to_f64());
+    d.iter()
+        .map(|&d| f16::from_f64(d.max(-max).min(max)))
+        .collect()
+}
+
+/// Computes the signed distance like [`signed_distance_2d`](../fn.signed_distance_2d.html) and
+/// stores it in the fixed-point format with `scale` units per grid step, that is, the value `v`
+/// in `out` stands for the distance `v / scale · h`.
+///
+/// For instance, with `scale = 256` the resolution is `h / 256` and the representable range is
+/// `[-32768 / 256, 32767 / 256] · h = [-128 h, 127.996 h]`. The values are rounded to the nearest
+/// representable value and saturate at the ends of the range; in particular, the "no interface"
+/// sentinels become `i16::MAX` and `i16::MIN`.
+pub fn signed_distance_fixed(out: &mut [i16], u: &[f64], dim: (usize, usize), scale: i32) {
+    assert_eq!(dim.0 * dim.1, out.len());
+    assert!(scale > 0, "The scale must be positive, was {}", scale);
+
+    let mut d = vec![0.; out.len()];
+    signed_distance_2d(&mut d, u, dim, 1.);
+    let s = f64::from(scale);
+    let (lo, hi) = (f64::from(std::i16::MIN), f64::from(std::i16::MAX));
+    for (o, &d) in out.iter_mut().zip(&d) {
+        *o = (d * s).round().max(lo).min(hi) as i16;
+    }
+}
+
+/// Computes the signed distance like [`signed_distance_2d`](../fn.signed_distance_2d.html) and
+/// stores it as a byte map, for instance for font or UI rendering on a GPU.
+///
+/// The distances `[-spread, spread]` are mapped linearly to `[0, 255]`, so the interface is at
+/// `128` (`127.5` before rounding) and the inside is darker than the outside. The values beyond
+/// the spread saturate to `0` and `255`.
+pub fn sdf_bytemap(u: &[f64], dim: (usize, usize), h: f64, spread: f64) -> Vec<u8> {
+    assert!(spread > 0., "The spread must be positive, was {}", spread);
+
+    let mut d = vec![0.; u.len()];
+    signed_distance_2d(&mut d, u, dim, h);
+    d.iter()
+        .map(|&d| ((d / spread + 1.) * 127.5).round().max(0.).min(255.) as u8)
+        .collect()
+}
+
+/// Run-length encodes the sign of the level set function `u` on a grid of dimensions `dim`.
+///
+/// Returns the runs `(inside, len)` of the nodes in the row-major order, where `inside` is
+/// `u < 0`, like in [`signed_distance_2d`](../fn.signed_distance_2d.html). Consecutive runs have
+/// different signs and the lengths add up to the number of nodes.
+pub fn sign_rle(u: &[f64], dim: (usize, usize)) -> Vec<(bool, usize)> {
+    assert_eq!(dim.0 * dim.1, u.len());
+
+    let mut runs: Vec<(bool, usize)> = vec![];
+    for &u in u {
+        let inside = u < 0.;
+        match runs.last_mut() {
+            Some(&mut (s, ref mut len)) if s == inside => *len += 1,
+            _ => runs.push((inside, 1)),
+        }
+    }
+    runs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bytemap_levels() {
+        let n = 33;
+        let h = 1. / (n - 1) as f64;
+        let mut u = vec![0.; n * n];
+        for i in 0..n {
+            for j in 0..n {
+                let (x, y) = (i as f64 * h - 0.5, j as f64 * h - 0.5);
+                u[i * n + j] = x.hypot(y) - 0.3;
+            }
+        }
+        let spread = 4. * h;
+        let b = sdf_bytemap(&u, (n, n), h, spread);
+        let mut d = vec![0.; n * n];
+        signed_distance_2d(&mut d, &u, (n, n), h);
+        for s in 0..n * n {
+            if d[s] <= -spread {
+                assert_eq!(b[s], 0);
+            } else if d[s] >= spread {
+                assert_eq!(b[s], 255);
+            } else {
+                let e = (d[s] / spread + 1.) * 127.5;
+                assert!((f64::from(b[s]) - e).abs() <= 0.5);
+            }
+            if d[s].abs() < 0.1 * h {
+                assert!(b[s] >= 124 && b[s] <= 132, "{}", b[s]);
+            }
+        }
+        // no interface
+        assert!(sdf_bytemap(&[1.; 9], (3, 3), h, spread)
+            .iter()
+            .all(|&b| b == 255));
+    }
+
+    #[test]
+    fn sign_rle_roundtrip() {
+        let n = 64;
+        let mut u = vec![0.; n * n];
+        for i in 0..n {
+            for j in 0..n {
+                let (x, y) = (i as f64 / n as f64 - 0.5, j as f64 / n as f64 - 0.5);
+                u[i * n + j] = x.hypot(y) - 0.25;
+            }
+        }
+        let rle = sign_rle(&u, (n, n));
+        let mut signs = vec![];
+        for &(inside, len) in &rle {
+            let end = signs.len() + len;
+            signs.resize(end, inside);
+        }
+        let dense: Vec<bool> = u.iter().map(|&u| u < 0.).collect();
+        assert_eq!(signs, dense);
+        for w in rle.windows(2) {
+            assert!(w[0].0 != w[1].0);
+        }
+        // at most 2 runs per row through the disk and one more
+        assert!(rle.len() <= 2 * n + 1);
+
+        assert_eq!(sign_rle(&[], (0, 0)), []);
+        assert_eq!(sign_rle(&[1.; 6], (2, 3)), [(false, 6)]);
+    }
+
+    #[test]
+    fn fixed_point_dequantizes() {
+        let n = 33;
+        let h = 1. / (n - 1) as f64;
+        let mut u = vec![0.; n * n];
+        for i in 0..n {
+            for j in 0..n {
+                let (x, y) = (i as f64 * h - 0.5, j as f64 * h - 0.45);
+                u[i * n + j] = x.hypot(y) - 0.3;
+            }
+        }
+        let mut d = vec![0.; n * n];
+        signed_distance_2d(&mut d, &u, (n, n), h);
+        let scale = 256;
+        let mut q = vec![0i16; n * n];
+        signed_distance_fixed(&mut q, &u, (n, n), scale);
+        for (&q, &d) in q.iter().zip(&d) {
+            let r = f64::from(q) / f64::from(scale) * h;
+            assert!((r - d).abs() <= 0.5 * h / f64::from(scale) + 1e-12);
+        }
+
+        // saturation
+        let u = vec![1.; 9];
+        let mut q = vec![0i16; 9];
+        signed_distance_fixed(&mut q, &u, (3, 3), scale);
+        assert!(q.iter().all(|&q| q == std::i16::MAX));
+        let u = vec![-1.; 9];
+        signed_distance_fixed(&mut q, &u, (3, 3), scale);
+        assert!(q.iter().all(|&q| q == std::i16::MIN));
+    }
+
+    #[cfg(feature = "half")]
+    #[test]
+    fn f16_sentinels_are_finite() {
+        let d = [0., -0.5, 1.25, std::f64::MAX, -std::f64::MAX];
+        let r = to_f16(&d);
+        assert_eq!(r[0].to_f64(), 0.);
+        assert_eq!(r[1].to_f64(), -0.5);
+        assert_eq!(r[2].to_f64(), 1.25);
+        assert_eq!(r[3], f16::MAX);
+        assert_eq!(r[4].to_f64(), -f16::MAX.to_f64());
+
+        let r = to_f16_clamped(&d, 1.);
+        let r: Vec<f64> = r.iter().map(|x| x.to_f64()).collect();
+        assert_eq!(r, [0., -0.5, 1., 1., -1.]);
+        assert!(to_f16_clamped(&d, 1e10).iter().all(|x| !x.is_infinite()));
+    }
+}
@@ -0,0 +1,298 @@
+//! Errors of the fallible `try_*` entry points.
+//!
+//! All `try_*` functions validate their input through the same checks before doing any work, and
+//! report the first problem found as a [`SweepError`](enum.SweepError.html). The panicking
+//! functions check the same conditions with assertions. The iterative solvers with a cap on the
+//! number of iterations report the failure to converge with the same error type.
+use std::error::Error;
+use std::fmt;
+
+/// Invalid input of a `try_*` function, or an iteration that did not converge.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SweepError {
+    /// The length of the named array does not match the grid dimensions.
+    LengthMismatch {
+        /// The name of the argument.
+        name: &'static str,
+        /// The number of nodes of the grid.
+        expected: usize,
+        /// The length of the array.
+        found: usize,
+    },
+    /// The grid is too small along an axis.
+    GridTooSmall {
+        /// The index of the axis.
+        axis: usize,
+        /// The number of nodes along the axis.
+        len: usize,
+        /// The minimal supported number of nodes.
+        min: usize,
+    },
+    /// The named array contains a `NaN` or an infinity.
+    NonFinite {
+        /// The name of the argument.
+        name: &'static str,
+        /// The index of the first value that is not finite.
+        index: usize,
+    },
+    /// The grid spacing is not a positive finite number.
+    InvalidSpacing(f64),
+    /// The speed is not a positive finite number.
+    InvalidSpeed {
+        /// The index of the first invalid speed.
+        index: usize,
+        /// The invalid speed.
+        value: f64,
+    },
+    /// A seed is outside of the grid or on a blocked node.
+    InvalidSeed {
+        /// The index of the first invalid seed.
+        index: usize,
+        /// The node `(i, j)` of the seed.
+        node: (usize, usize),
+    },
+    /// The iteration reached the maximal number of rounds without converging.
+    NotConverged {
+        /// The number of sweeps performed.
+        sweeps: usize,
+        /// The largest change of a value over the last round.
+        change: f64,
+    },
+}
+
+impl fmt::Display for SweepError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SweepError::LengthMismatch {
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "`{}` has length {}, but the grid has {} nodes",
+                name, found, expected
+            ),
+            SweepError::GridTooSmall { axis, len, min } => write!(
+                f,
+                "the grid must have at least {} nodes along axis {}, has {}",
+                min, axis, len
+            ),
+            SweepError::NonFinite { name, index } => {
+                write!(f, "`{}` is not finite at index {}", name, index)
+            }
+            SweepError::InvalidSpacing(h) => {
+                write!(f, "the grid spacing must be positive and finite, was {}", h)
+            }
+            SweepError::InvalidSpeed { index, value } => write!(
+                f,
+                "the speed must be positive and finite, was {} at index {}",
+                value, index
+            ),
+            SweepError::InvalidSeed { index, node } => write!(
+                f,
+                "the seed {} at {:?} is outside of the grid or blocked",
+                index, node
+            ),
+            SweepError::NotConverged { sweeps, change } => write!(
+                f,
+                "no convergence after {} sweeps, the last change was {}",
+                sweeps, change
+            ),
+        }
+    }
+}
+
+impl Error for SweepError {}
+
+/// The shared input validation of the `try_*` functions for a grid with `n` nodes.
+pub(crate) struct Preflight {
+    n: usize,
+}
+
+impl Preflight {
+    pub(crate) fn new(n: usize) -> Preflight {
+        Preflight { n }
+    }
+
+    /// Checks that the grid has at least `min` nodes along every axis.
+    pub(crate) fn min_dim(&self, dim: &[usize], min: usize) -> Result<&Self, SweepError> {
+        match dim.iter().position(|&n| n < min) {
+            Some(axis) => Err(SweepError::GridTooSmall {
+                axis,
+                len: dim[axis],
+                min,
+            }),
+            None => Ok(self),
+        }
+    }
+
+    /// Checks that the array `name` has one value per node.
+    pub(crate) fn len(&self, name: &'static str, len: usize) -> Result<&Self, SweepError> {
+        if len == self.n {
+            Ok(self)
+        } else {
+            Err(SweepError::LengthMismatch {
+                name,
+                expected: self.n,
+                found: len,
+            })
+        }
+    }
+
+    /// Checks that the array `name` has one value per node and that all of them are finite.
+    pub(crate) fn finite(&self, name: &'static str, v: &[f64]) -> Result<&Self, SweepError> {
+        self.len(name, v.len())?;
+        match v.iter().position(|v| !v.is_finite()) {
+            Some(index) => Err(SweepError::NonFinite { name, index }),
+            None => Ok(self),
+        }
+    }
+
+    /// Checks that the grid spacing is positive and finite.
+    pub(crate) fn spacing(&self, h: f64) -> Result<&Self, SweepError> {
+        if h > 0. && h.is_finite() {
+            Ok(self)
+        } else {
+            Err(SweepError::InvalidSpacing(h))
+        }
+    }
+
+    /// Checks that there is one speed per node and that the speeds are positive and finite, except
+    /// at the nodes where `skip` is true.
+    pub(crate) fn speed<F>(&self, speed: &[f64], skip: F) -> Result<&Self, SweepError>
+    where
+        F: Fn(usize) -> bool,
+    {
+        self.len("speed", speed.len())?;
+        let valid = |c: f64| c > 0. && c.is_finite();
+        match speed
+            .iter()
+            .enumerate()
+            .position(|(s, &c)| !skip(s) && !valid(c))
+        {
+            Some(index) => Err(SweepError::InvalidSpeed {
+                index,
+                value: speed[index],
+            }),
+            None => Ok(self),
+        }
+    }
+
+    /// Checks that the `seeds`, the nodes `(i, j)` of the grid of dimensions `dim`, lie on the
+    /// grid and that `blocked` is false at all of them.
+    pub(crate) fn seeds<I, F>(
+        &self,
+        dim: (usize, usize),
+        seeds: I,
+        blocked: F,
+    ) -> Result<&Self, SweepError>
+    where
+        I: IntoIterator<Item = (usize, usize)>,
+        F: Fn(usize) -> bool,
+    {
+        match seeds
+            .into_iter()
+            .enumerate()
+            .find(|&(_, (i, j))| i >= dim.0 || j >= dim.1 || blocked(i * dim.1 + j))
+        {
+            Some((index, node)) => Err(SweepError::InvalidSeed { index, node }),
+            None => Ok(self),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use voronoi::try_geodesic_voronoi;
+    use {try_signed_distance_2d, try_signed_distance_3d};
+
+    #[test]
+    fn every_variant_is_reported() {
+        let mut u = [1.; 9];
+        u[4] = -1.;
+        let mut d = [0.; 9];
+        assert_eq!(try_signed_distance_2d(&mut d, &u, (3, 3), 0.5), Ok(()));
+        assert_eq!(
+            try_signed_distance_2d(&mut d, &u, (3, 4), 0.5),
+            Err(SweepError::LengthMismatch {
+                name: "u",
+                expected: 12,
+                found: 9
+            })
+        );
+        assert_eq!(
+            try_signed_distance_2d(&mut d[..8], &u, (3, 3), 0.5),
+            Err(SweepError::LengthMismatch {
+                name: "d",
+                expected: 9,
+                found: 8
+            })
+        );
+        assert_eq!(
+            try_signed_distance_3d(&mut d, &u, (1, 3, 3), 0.5),
+            Err(SweepError::GridTooSmall {
+                axis: 0,
+                len: 1,
+                min: 2
+            })
+        );
+        let mut d3 = [0.; 8];
+        assert_eq!(
+            try_signed_distance_3d(&mut d3, &[-1., 1., 1., 1., 1., 1., 1., 1.], (2, 2, 2), 0.5),
+            Ok(())
+        );
+        assert_eq!(
+            try_signed_distance_3d(&mut d3[..7], &[1.; 8], (2, 2, 2), 0.5),
+            Err(SweepError::LengthMismatch {
+                name: "d",
+                expected: 8,
+                found: 7
+            })
+        );
+        let mut nan = u;
+        nan[1] = std::f64::NAN;
+        assert_eq!(
+            try_signed_distance_2d(&mut d, &nan, (3, 3), 0.5),
+            Err(SweepError::NonFinite {
+                name: "u",
+                index: 1
+            })
+        );
+        assert_eq!(
+            try_signed_distance_2d(&mut d, &u, (3, 3), 0.),
+            Err(SweepError::InvalidSpacing(0.))
+        );
+
+        let mut label = [0; 4];
+        let mut time = [0.; 4];
+        let blocked = [false, false, true, false];
+        let speed = [1., 1., 0., -2.];
+        assert_eq!(
+            try_geodesic_voronoi(
+                &mut label,
+                &mut time,
+                &[(0, 0, 1)],
+                &speed,
+                &blocked,
+                (2, 2),
+                0.5
+            ),
+            Err(SweepError::InvalidSpeed {
+                index: 3,
+                value: -2.
+            })
+        );
+        let speed = [1., 1., 0., 2.];
+        for &(seeds, index, node) in &[
+            (&[(1, 0, 1)][..], 0, (1, 0)),
+            (&[(0, 0, 1), (0, 2, 2)][..], 1, (0, 2)),
+        ] {
+            assert_eq!(
+                try_geodesic_voronoi(&mut label, &mut time, seeds, &speed, &blocked, (2, 2), 0.5),
+                Err(SweepError::InvalidSeed { index, node })
+            );
+        }
+        assert!(format!("{}", SweepError::InvalidSpacing(-1.)).contains("-1"));
+    }
+}
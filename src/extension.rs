@@ -0,0 +1,251 @@
+//! Extension of quantities from the interface along the characteristics of the distance function.
+//!
+//! A quantity `q` given at the nodes next to the interface is extended to the whole grid so that
+//! it is constant along the characteristics of the distance function `d`, that is, `∇d · ∇q = 0`.
+//! The upwind discretization gives at every other node
+//!
+//! q = Σ wₖ qₖ / Σ wₖ,   wₖ = (|d| - |dₖ|)₊,
+//!
+//! where `k` goes over the neighbor with the smaller `|d|` along each axis.
+//!
+//! The _interface nodes_ are the nodes with `d = 0` or with a horizontal or vertical neighbor of
+//! the opposite sign. The values of `q` there are kept, the values elsewhere are overwritten.
+use super::signed_from_eikonal;
+use level_set::init_dist_2d;
+use {eikonal, DualNorm, EuclideanNorm};
+
+/// Marks the interface nodes of a 2D grid, given whether the level set function is negative at a
+/// node and whether it is zero.
+fn interface_nodes<F, G>(dim: (usize, usize), neg: F, zero: G) -> Vec<bool>
+where
+    F: Fn(usize) -> bool,
+    G: Fn(usize) -> bool,
+{
+    let (ni, nj) = dim;
+    let mut fixed = vec![false; ni * nj];
+    for i in 0..ni {
+        for j in 0..nj {
+            let s = i * nj + j;
+            let differs = |t: usize| neg(t) != neg(s);
+            fixed[s] = zero(s)
+                || (i > 0 && differs(s - nj))
+                || (i + 1 < ni && differs(s + nj))
+                || (j > 0 && differs(s - 1))
+                || (j + 1 < nj && differs(s + 1));
+        }
+    }
+    fixed
+}
+
+/// Upwind update of `q` at node `(i, j)` from the unsigned distance `d`. Returns `None` if there
+/// is no upwind neighbor.
+#[inline(always)]
+fn extend_at(q: &[f64], d: &[f64], dim: (usize, usize), i: usize, j: usize) -> Option<f64> {
+    let (ni, nj) = dim;
+    let s = i * nj + j;
+    let (mut num, mut den) = (0., 0.);
+    for &(has_lo, has_hi, stride) in &[(i > 0, i + 1 < ni, nj), (j > 0, j + 1 < nj, 1)] {
+        let k = match (has_lo, has_hi) {
+            (true, true) if d[s + stride] < d[s - stride] => s + stride,
+            (true, _) => s - stride,
+            (false, true) => s + stride,
+            (false, false) => continue,
+        };
+        if d[k] < d[s] {
+            let w = d[s] - d[k];
+            num += w * q[k];
+            den += w;
+        }
+    }
+    if den > 0. && den < std::f64::INFINITY {
+        Some(num / den)
+    } else {
+        None
+    }
+}
+
+/// Sweeps `q` in the four diagonal directions. Returns whether any value changed.
+fn sweep_q(q: &mut [f64], d: &[f64], fixed: &[bool], dim: (usize, usize)) -> bool {
+    let mut changed = false;
+    for &dir in &eikonal::SWEEP_DIRS_2D {
+        eikonal::visit_2d(dim, dim.1, dir, |s, (i, j)| {
+            if fixed[s] {
+                return;
+            }
+            if let Some(v) = extend_at(q, d, dim, i, j) {
+                // bitwise, so that a NaN does not count as a change every time
+                if v.to_bits() != q[s].to_bits() {
+                    q[s] = v;
+                    changed = true;
+                }
+            }
+        });
+    }
+    changed
+}
+
+/// Extends the values of `q` at the interface nodes of the signed distance function `d` on a
+/// regular 2D grid of dimensions `dim` to the whole grid.
+///
+/// The sweeps are repeated until `q` does not change anymore. Nodes that cannot be reached from
+/// the interface, for instance when `d` is the "no interface" sentinel everywhere, keep their
+/// value. A NaN at an interface node spreads along the characteristics like any other value.
+pub fn extend_velocity(q: &mut [f64], d: &[f64], dim: (usize, usize)) {
+    assert_eq!(dim.0 * dim.1, q.len());
+    assert_eq!(dim.0 * dim.1, d.len());
+
+    let fixed = interface_nodes(dim, |s| d[s] < 0., |s| d[s] == 0.);
+    let dist: Vec<f64> = d.iter().map(|d| d.abs()).collect();
+    while sweep_q(q, &dist, &fixed, dim) {}
+}
+
+/// Computes the signed distance like [`signed_distance_2d`](../fn.signed_distance_2d.html) and at
+/// the same time extends the values of `q` at the interface nodes to the whole grid, see
+/// [`extend_velocity`](fn.extend_velocity.html).
+///
+/// Both are updated in the same Gauss-Seidel sweeps, which are repeated until neither of them
+/// changes. The result is the same as that of `signed_distance_2d` followed by `extend_velocity`
+/// up to rounding, but `q` typically settles in the same passes as the distance instead of needing
+/// passes of its own.
+pub fn signed_distance_2d_extend(
+    d: &mut [f64],
+    q: &mut [f64],
+    u: &[f64],
+    dim: (usize, usize),
+    h: f64,
+) {
+    let (ni, nj) = dim;
+    assert_eq!(ni * nj, u.len());
+    assert_eq!(ni * nj, d.len());
+    assert_eq!(ni * nj, q.len());
+
+    init_dist_2d(d, u, dim, |p| EuclideanNorm.dual_norm(p));
+    let fixed = interface_nodes(dim, |s| u[s] < 0., |s| u[s] == 0.);
+
+    loop {
+        let mut changed = false;
+        for &dir in &eikonal::SWEEP_DIRS_2D {
+            eikonal::visit_2d(dim, nj, dir, |s, (i, j)| {
+                let v = eikonal::upwind_2d(d, dim, s, (i, j), dir, |_| false);
+                let t = EuclideanNorm.inv_dual_norm(d[s], v, [dir.0 as f64, dir.1 as f64]);
+                if t < d[s] {
+                    d[s] = t;
+                    changed = true;
+                }
+                if !fixed[s] {
+                    if let Some(v) = extend_at(q, d, dim, i, j) {
+                        if v.to_bits() != q[s].to_bits() {
+                            q[s] = v;
+                            changed = true;
+                        }
+                    }
+                }
+            });
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    signed_from_eikonal(d, u, h);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use signed_distance_2d;
+
+    #[test]
+    fn extend_matches_separate_passes() {
+        let n = 33;
+        let h = 1. / (n - 1) as f64;
+        let mut u = vec![0.; n * n];
+        let mut q0 = vec![0.; n * n];
+        for i in 0..n {
+            for j in 0..n {
+                let (x, y) = (i as f64 * h - 0.45, j as f64 * h - 0.5);
+                u[i * n + j] = x.hypot(y) - 0.3;
+                q0[i * n + j] = x + 2. * y;
+            }
+        }
+
+        let mut d = vec![0.; n * n];
+        let mut q = q0.clone();
+        signed_distance_2d_extend(&mut d, &mut q, &u, (n, n), h);
+
+        let mut d_sep = vec![0.; n * n];
+        let mut q_sep = q0.clone();
+        signed_distance_2d(&mut d_sep, &u, (n, n), h);
+        extend_velocity(&mut q_sep, &d_sep, (n, n));
+
+        for s in 0..n * n {
+            assert!((d[s] - d_sep[s]).abs() < 1e-12, "{} != {}", d[s], d_sep[s]);
+            assert!((q[s] - q_sep[s]).abs() < 1e-9, "{} != {}", q[s], q_sep[s]);
+        }
+        // the interface values are kept and the extension is bounded by them
+        let fixed = interface_nodes((n, n), |s| u[s] < 0., |s| u[s] == 0.);
+        let (lo, hi) = (0..n * n)
+            .filter(|&s| fixed[s])
+            .fold((1f64, -1f64), |(lo, hi), s| (lo.min(q0[s]), hi.max(q0[s])));
+        for s in 0..n * n {
+            if fixed[s] {
+                assert_eq!(q[s], q0[s]);
+            }
+            assert!(q[s] >= lo - 1e-12 && q[s] <= hi + 1e-12);
+        }
+    }
+
+    #[test]
+    fn extension_is_constant_along_normals() {
+        // a straight interface x = 0.3: the extension is constant along the rows
+        let n = 17;
+        let h = 1. / (n - 1) as f64;
+        let mut d = vec![0.; n * n];
+        let mut q = vec![0.; n * n];
+        for i in 0..n {
+            for j in 0..n {
+                d[i * n + j] = i as f64 * h - 0.3;
+                q[i * n + j] = if (i as f64 * h - 0.3).abs() < h {
+                    (j as f64).sin()
+                } else {
+                    0.
+                };
+            }
+        }
+        extend_velocity(&mut q, &d, (n, n));
+        for i in 0..n {
+            for j in 0..n {
+                assert!((q[i * n + j] - (j as f64).sin()).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn nan_spreads_and_stops() {
+        // a straight interface x = 0.3 with a NaN at the interface node (4, 7)
+        let n = 17;
+        let h = 1. / (n - 1) as f64;
+        let mut u = vec![0.; n * n];
+        let mut q = vec![0.; n * n];
+        for i in 0..n {
+            for j in 0..n {
+                u[i * n + j] = i as f64 * h - 0.3;
+                q[i * n + j] = (j as f64).sin();
+            }
+        }
+        q[4 * n + 7] = std::f64::NAN;
+        let q0 = q.clone();
+        extend_velocity(&mut q, &u, (n, n));
+        for i in 0..n {
+            for j in 0..n {
+                let s = i * n + j;
+                assert_eq!(q[s].is_nan(), j == 7 && i <= 4, "{:?}", (i, j));
+            }
+        }
+
+        let mut d = vec![0.; n * n];
+        let mut q = q0;
+        signed_distance_2d_extend(&mut d, &mut q, &u, (n, n), h);
+        assert!(q[7].is_nan());
+    }
+}
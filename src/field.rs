@@ -0,0 +1,175 @@
+//! An owning signed distance field on a regular 2D grid and the interpolation of grid values at
+//! arbitrary points.
+
+use signed_distance_2d;
+
+/// The values of a signed distance function on a regular 2D grid together with the dimensions of
+/// the grid and its spacing.
+///
+/// The node `(i, j)` is at `(i h, j h)` and `data` is in the _row-major_ order (C order).
+///
+/// With the `serde` feature the field implements `Serialize` and `Deserialize`, with the
+/// dimensions and the spacing stored next to the values, so a saved field can be loaded without
+/// any other information. The length of the loaded `data` is not checked against `dim`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DistanceField {
+    /// The values at the nodes.
+    pub data: Vec<f64>,
+    /// The dimensions of the grid.
+    pub dim: (usize, usize),
+    /// The distance between neighboring nodes.
+    pub h: f64,
+}
+
+impl DistanceField {
+    /// Computes the signed distance from the _zero_ level set of `u` like
+    /// [`signed_distance_2d`](../fn.signed_distance_2d.html).
+    pub fn from_level_set(u: &[f64], dim: (usize, usize), h: f64) -> DistanceField {
+        let mut data = vec![0.; dim.0 * dim.1];
+        signed_distance_2d(&mut data, u, dim, h);
+        DistanceField { data, dim, h }
+    }
+
+    /// The value at the node `(i, j)`.
+    pub fn at(&self, i: usize, j: usize) -> f64 {
+        assert!(
+            i < self.dim.0 && j < self.dim.1,
+            "The node ({}, {}) is outside the grid",
+            i,
+            j
+        );
+        self.data[i * self.dim.1 + j]
+    }
+
+    /// Replaces the values by the signed distance from their own _zero_ level set, for instance
+    /// after they have been moved by a level set evolution.
+    pub fn reinitialize(&mut self) {
+        let u = self.data.clone();
+        signed_distance_2d(&mut self.data, &u, self.dim, self.h);
+    }
+
+    /// The bilinear interpolation of the values at the point `(x, y)`, see
+    /// [`sample_2d`](fn.sample_2d.html).
+    pub fn sample(&self, x: f64, y: f64) -> f64 {
+        sample_2d(&self.data, self.dim, self.h, x, y)
+    }
+}
+
+/// The lower node of the cell of a grid with `n` nodes and the coordinate relative to it of the
+/// point at `t` grid units, clamped to the grid. The last cell is used at the far boundary.
+fn cell(t: f64, n: usize) -> (usize, usize, f64) {
+    let t = t.max(0.).min((n - 1) as f64);
+    let i = std::cmp::min(t as usize, n.saturating_sub(2));
+    (i, std::cmp::min(i + 1, n - 1), t - i as f64)
+}
+
+/// The bilinear interpolation of the values `d` on a regular 2D grid with spacing `h` at the point
+/// `(x, y)`, the node `(i, j)` being at `(i h, j h)`. The points outside of the grid are clamped
+/// to its boundary.
+pub fn sample_2d(d: &[f64], dim: (usize, usize), h: f64, x: f64, y: f64) -> f64 {
+    let (ni, nj) = dim;
+    assert_eq!(ni * nj, d.len());
+    assert!(ni >= 1 && nj >= 1, "The grid is empty");
+    let (i0, i1, a) = cell(x / h, ni);
+    let (j0, j1, b) = cell(y / h, nj);
+    let at = |i: usize, j: usize| d[i * nj + j];
+    (1. - a) * ((1. - b) * at(i0, j0) + b * at(i0, j1))
+        + a * ((1. - b) * at(i1, j0) + b * at(i1, j1))
+}
+
+/// The trilinear interpolation of the values `d` on a regular 3D grid with spacing `h` at the
+/// point `p`, the node `(i, j, k)` being at `(i h, j h, k h)`. The points outside of the grid are
+/// clamped to its boundary.
+pub fn sample_3d(d: &[f64], dim: (usize, usize, usize), h: f64, p: [f64; 3]) -> f64 {
+    let (ni, nj, nk) = dim;
+    assert_eq!(ni * nj * nk, d.len());
+    assert!(ni >= 1 && nj >= 1 && nk >= 1, "The grid is empty");
+    let (i0, i1, a) = cell(p[0] / h, ni);
+    let (j0, j1, b) = cell(p[1] / h, nj);
+    let (k0, k1, c) = cell(p[2] / h, nk);
+    let at = |i: usize, j: usize, k: usize| d[(i * nj + j) * nk + k];
+    let plane = |k: usize| {
+        (1. - a) * ((1. - b) * at(i0, j0, k) + b * at(i0, j1, k))
+            + a * ((1. - b) * at(i1, j0, k) + b * at(i1, j1, k))
+    };
+    (1. - c) * plane(k0) + c * plane(k1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn field_of_circle() {
+        let n = 21;
+        let h = 0.05;
+        let mut u = vec![0.; n * n];
+        for i in 0..n {
+            for j in 0..n {
+                u[i * n + j] = (i as f64 * h - 0.5).hypot(j as f64 * h - 0.5) - 0.3;
+            }
+        }
+        let mut field = DistanceField::from_level_set(&u, (n, n), h);
+        let mut d = vec![0.; n * n];
+        signed_distance_2d(&mut d, &u, (n, n), h);
+        assert_eq!(field.data, d);
+        assert_eq!(field.at(3, 4), d[3 * n + 4]);
+
+        assert!((field.sample(3. * h, 4. * h) - field.at(3, 4)).abs() < 1e-12);
+        let mid = 0.25 * (field.at(3, 4) + field.at(3, 5) + field.at(4, 4) + field.at(4, 5));
+        assert!((field.sample(3.5 * h, 4.5 * h) - mid).abs() < 1e-12);
+        assert_eq!(field.sample(1., 1.), field.at(n - 1, n - 1));
+        assert_eq!(field.sample(-1., 0.), field.at(0, 0));
+
+        // linear functions are reproduced exactly
+        let lin: Vec<f64> = (0..n * n)
+            .map(|s| 2. * (s / n) as f64 * h - 3. * (s % n) as f64 * h)
+            .collect();
+        assert!((sample_2d(&lin, (n, n), h, 0.123, 0.456) - (0.246 - 1.368)).abs() < 1e-12);
+        let (ni, nj, nk) = (4, 5, 6);
+        let lin: Vec<f64> = (0..ni * nj * nk)
+            .map(|s| ((s / (nj * nk)) + 2 * (s / nk % nj) + 3 * (s % nk)) as f64 * h)
+            .collect();
+        let p = [0.07, 0.13, 0.21];
+        let e = p[0] + 2. * p[1] + 3. * p[2];
+        assert!((sample_3d(&lin, (ni, nj, nk), h, p) - e).abs() < 1e-12);
+        let corner = lin[lin.len() - 1];
+        assert!((sample_3d(&lin, (ni, nj, nk), h, [1.; 3]) - corner).abs() < 1e-12);
+
+        // a steeper function with the same zero level set
+        let reference = field.clone();
+        for v in &mut field.data {
+            *v *= 3.;
+        }
+        field.reinitialize();
+        for (a, b) in field.data.iter().zip(&reference.data) {
+            assert!((a - b).abs() < 0.5 * h, "{} != {}", a, b);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json_round_trip() {
+        let n = 5;
+        let round_trip = |u: &[f64]| {
+            let field = DistanceField::from_level_set(u, (n, n), 0.1);
+            let json = ::serde_json::to_string(&field).unwrap();
+            assert!(json.contains(r#""dim":[5,5]"#) && json.contains(r#""h":0.1"#));
+            let loaded: DistanceField = ::serde_json::from_str(&json).unwrap();
+            assert_eq!((loaded.dim, loaded.h), (field.dim, field.h));
+            // the decimal representation may lose the last bit
+            for (a, b) in loaded.data.iter().zip(&field.data) {
+                assert!(
+                    a.is_finite() && (a - b).abs() <= 1e-15 * b.abs(),
+                    "{} != {}",
+                    a,
+                    b
+                );
+            }
+        };
+        round_trip(&(0..n * n).map(|s| (s / n) as f64 - 2.3).collect::<Vec<_>>());
+        // the sentinels of a field without an interface
+        round_trip(&vec![1.; n * n]);
+    }
+}
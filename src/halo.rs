@@ -0,0 +1,177 @@
+//! Domain decomposition with halo exchange, for instance for distributed-memory (MPI) runs.
+//!
+//! Each process owns a subgrid of the global 2D grid and stores it together with a _halo_: one
+//! layer of nodes around it with copies of the values owned by the neighboring subgrids. The local
+//! array of dimensions `dim` includes the halo, so the owned nodes are `1..dim.0 - 1` by
+//! `1..dim.1 - 1`. Along the edges of the global domain the halo should be `std::f64::MAX`.
+//!
+//! The edges are numbered like in the [`boundary` module](../boundary/index.html):
+//! `[i = 0, i = ni - 1, j = 0, j = nj - 1]`.
+//!
+//! An external driver initializes the distance near the interface (for instance with
+//! [`init_dist_2d`](../level_set/fn.init_dist_2d.html) on the local part of the level set function
+//! including the halo) and then repeats
+//!
+//! 1. [`sweep_subgrid_2d`](fn.sweep_subgrid_2d.html) on every subgrid,
+//! 2. sending the [`owned_edge`](fn.owned_edge.html) layers to the neighbors and
+//! 3. merging the received layers with [`merge_halo`](fn.merge_halo.html),
+//!
+//! until no halo changes anymore. The result is the same as the solution on the whole grid.
+use eikonal;
+
+/// Returns the index of the first node, the stride and the number of nodes of the layer `k`
+/// counted from the edge number `edge` (the halo is the layer 0).
+#[inline(always)]
+fn layer(dim: (usize, usize), edge: usize, k: usize) -> (usize, usize, usize) {
+    let (ni, nj) = dim;
+    match edge {
+        0 => (k * nj, 1, nj),
+        1 => ((ni - 1 - k) * nj, 1, nj),
+        2 => (k, nj, ni),
+        3 => (nj - 1 - k, nj, ni),
+        _ => panic!("The edge must be 0, 1, 2 or 3, was {}", edge),
+    }
+}
+
+/// Sweeps the owned nodes of the local subgrid `d` (including the halo) in the four diagonal
+/// directions until nothing changes, reading but never modifying the halo.
+///
+/// `inv_norm` is the same as for [`fast_sweep_2d`](../eikonal/fn.fast_sweep_2d.html).
+///
+/// Returns for every edge whether the owned layer next to it changed, that is, whether the
+/// neighbor across that edge needs to receive it.
+pub fn sweep_subgrid_2d<F>(d: &mut [f64], dim: (usize, usize), inv_norm: F) -> [bool; 4]
+where
+    F: Fn(f64, [f64; 2], [f64; 2]) -> f64,
+{
+    let (ni, nj) = dim;
+    assert_eq!(ni * nj, d.len());
+    assert!(
+        ni >= 3 && nj >= 3,
+        "The subgrid with the halo must be at least (3, 3), was ({}, {})",
+        ni,
+        nj
+    );
+
+    let mut edges = [false; 4];
+    let halo = |s: usize| s < nj || s >= (ni - 1) * nj || s % nj == 0 || s % nj == nj - 1;
+    eikonal::sweep_2d_repeat(d, dim, 0., halo, |d, s, (i, j), dir| {
+        // the owned nodes have all their neighbors in the local array
+        let v = eikonal::upwind_2d(d, dim, s, (i, j), dir, |_| false);
+        let t = inv_norm(d[s], v, [dir.0 as f64, dir.1 as f64]);
+        if t < d[s] {
+            edges[0] |= i == 1;
+            edges[1] |= i == ni - 2;
+            edges[2] |= j == 1;
+            edges[3] |= j == nj - 2;
+        }
+        t
+    });
+    edges
+}
+
+/// Returns the owned layer of the local subgrid `d` next to the edge `edge`, including the two
+/// halo nodes at its ends. This is the halo across that edge of the neighboring subgrid.
+pub fn owned_edge(d: &[f64], dim: (usize, usize), edge: usize) -> Vec<f64> {
+    assert_eq!(dim.0 * dim.1, d.len());
+    let (start, step, len) = layer(dim, edge, 1);
+    (0..len).map(|p| d[start + p * step]).collect()
+}
+
+/// Merges the values received from the neighbor across the edge `edge` into the halo of the local
+/// subgrid `d`, keeping the smaller value at every node.
+///
+/// Returns whether the halo changed, that is, whether the subgrid needs to be swept again.
+pub fn merge_halo(d: &mut [f64], dim: (usize, usize), edge: usize, values: &[f64]) -> bool {
+    assert_eq!(dim.0 * dim.1, d.len());
+    let (start, step, len) = layer(dim, edge, 0);
+    assert_eq!(len, values.len());
+
+    let mut changed = false;
+    for (p, &v) in values.iter().enumerate() {
+        let s = start + p * step;
+        if v < d[s] {
+            d[s] = v;
+            changed = true;
+        }
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use boundary::{fast_sweep_2d_bc, BoundaryCondition};
+    use level_set::init_dist_2d;
+    use {DualNorm, EuclideanNorm};
+
+    #[test]
+    fn two_ranks_match_monolithic() {
+        let (ni, nj) = (24, 19);
+        let h = 1. / (nj - 1) as f64;
+        let mut u = vec![0.; ni * nj];
+        for i in 0..ni {
+            for j in 0..nj {
+                // two circles, one in each half
+                let (x, y) = (i as f64 * h, j as f64 * h);
+                let a = (x - 0.3).hypot(y - 0.4) - 0.15;
+                let b = (x - 0.9).hypot(y - 0.7) - 0.2;
+                u[i * nj + j] = a.min(b);
+            }
+        }
+        let inv_norm = |d, v, s| EuclideanNorm.inv_dual_norm(d, v, s);
+        let mut init = vec![0.; ni * nj];
+        init_dist_2d(&mut init, &u, (ni, nj), |p| EuclideanNorm.dual_norm(p));
+        let mut expected = init.clone();
+        fast_sweep_2d_bc(
+            &mut expected,
+            (ni, nj),
+            [BoundaryCondition::Open; 4],
+            inv_norm,
+        );
+
+        // rank 0 owns the rows 0..split, rank 1 the rows split..ni
+        let split = 10;
+        let local = |rows: ::std::ops::Range<usize>| {
+            let dim = (rows.end - rows.start + 2, nj + 2);
+            let mut d = vec![std::f64::MAX; dim.0 * dim.1];
+            for (a, i) in rows.enumerate() {
+                d[(a + 1) * dim.1 + 1..][..nj].copy_from_slice(&init[i * nj..][..nj]);
+            }
+            (d, dim)
+        };
+        let (mut d0, dim0) = local(0..split);
+        let (mut d1, dim1) = local(split..ni);
+
+        let mut rounds = 0;
+        loop {
+            rounds += 1;
+            let e0 = sweep_subgrid_2d(&mut d0, dim0, inv_norm);
+            let e1 = sweep_subgrid_2d(&mut d1, dim1, inv_norm);
+            let mut changed = false;
+            if e0[1] || rounds == 1 {
+                changed |= merge_halo(&mut d1, dim1, 0, &owned_edge(&d0, dim0, 1));
+            }
+            if e1[0] || rounds == 1 {
+                changed |= merge_halo(&mut d0, dim0, 1, &owned_edge(&d1, dim1, 0));
+            }
+            if !changed {
+                break;
+            }
+        }
+        assert!(rounds > 1);
+
+        for i in 0..ni {
+            let (d, dim, a) = if i < split {
+                (&d0, dim0, i + 1)
+            } else {
+                (&d1, dim1, i - split + 1)
+            };
+            for j in 0..nj {
+                let v = d[a * dim.1 + j + 1];
+                let e = expected[i * nj + j];
+                assert!((v - e).abs() < 1e-12, "({}, {}): {} != {}", i, j, v, e);
+            }
+        }
+    }
+}
@@ -21,6 +21,10 @@
 //! distance, or `anisotropic_signed_distance_2d`, `anisotropic_signed_distance_3d` for other
 //! norms.
 //!
+//! The crate needs `std`: besides the slice-based solvers it depends on `ndarray` and
+//! `isosurface`, and some modules use `HashMap` and `std::error::Error`. A `no_std` build is not
+//! supported.
+//!
 //! The algorithm finds the distance function _d_ by solving the eikonal equation
 //!
 //! ‖∇d‖_* = 1
@@ -69,15 +73,59 @@
 
 #![deny(missing_docs)]
 
+#[cfg(feature = "half")]
+extern crate half;
+#[cfg(feature = "image")]
+extern crate image;
 extern crate isosurface;
 extern crate ndarray;
+#[cfg(feature = "parallel")]
+extern crate ndarray_parallel;
+extern crate num_traits;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_json;
 
+pub mod boundary;
+pub mod closest_point;
+pub mod contour;
+pub mod derivatives;
+pub mod diagnostics;
 pub mod dist;
+pub mod drift;
 pub mod eikonal;
+pub mod encode;
+pub mod error;
+pub mod extension;
+pub mod field;
+pub mod halo;
 pub mod level_set;
+pub mod measure;
 pub mod norm;
+#[cfg(feature = "npy")]
+pub mod npy;
+pub mod ops;
+pub mod polygon;
+#[cfg(feature = "image")]
+pub mod raster;
+pub mod reinit;
+pub mod scattered;
+pub mod transform;
+pub mod voronoi;
+#[cfg(feature = "vtk")]
+pub mod vtk;
+
+pub use boundary::BoundaryCondition;
+pub use eikonal::EikonalSolver;
+pub use error::SweepError;
+pub use field::DistanceField;
+pub use level_set::{tetrahedron_dist, triangle_dist};
+pub use norm::{AffineNorm, DualNorm, EuclideanNorm, L1Norm, MaxNorm};
 
-pub use norm::{DualNorm, EuclideanNorm, L1Norm, MaxNorm};
+#[cfg(feature = "ndarray")]
+use ndarray::{ArrayView2, ArrayViewMut2};
 
 /// Computes the signed distance from the _zero_ level set of the function given by the values of
 /// `u` on a regular 2D grid of dimensions `dim` and stores the result in a preallocated array `d`.
@@ -100,9 +148,505 @@ pub use norm::{DualNorm, EuclideanNorm, L1Norm, MaxNorm};
 ///
 /// `u` is assumed to be in the _row-major_ order (C order).
 ///
-/// Returns `std::f64::MAX` if all `u` are nonnegative (`-std::f64::MAX` if all `u` are negative).
-pub fn signed_distance_2d(d: &mut [f64], u: &[f64], dim: (usize, usize), h: f64) {
-    anisotropic_signed_distance_2d(d, u, dim, h, EuclideanNorm);
+/// Returns whether the zero level set crosses the grid. If it does not, all of `d` is set to
+/// `std::f64::MAX` if all `u` are nonnegative (`-std::f64::MAX` if all `u` are negative), so
+/// the return value tells this case apart without scanning `u` or `d`.
+///
+/// A grid with a single row or column is solved by
+/// [`signed_distance_1d`](fn.signed_distance_1d.html), and an empty grid is left as is.
+///
+/// Panics if the lengths of `d` and `u` do not match `dim`; use
+/// [`try_signed_distance_2d`](fn.try_signed_distance_2d.html) to get an error instead.
+pub fn signed_distance_2d(d: &mut [f64], u: &[f64], dim: (usize, usize), h: f64) -> bool {
+    if dim.0 <= 1 || dim.1 <= 1 {
+        assert_eq!(dim.0 * dim.1, u.len());
+        return signed_distance_1d(d, u, h);
+    }
+    anisotropic_signed_distance_2d(d, u, dim, h, EuclideanNorm)
+}
+
+/// Computes the signed distance like [`signed_distance_2d`](fn.signed_distance_2d.html), solving
+/// the eikonal equation with `solver`, for instance
+/// [`eikonal::FastMarching`](eikonal/struct.FastMarching.html).
+///
+/// The initialization near the interface is the same for all solvers, so the solvers can be
+/// compared head to head. Returns whether the zero level set crosses the grid.
+pub fn signed_distance_2d_with<S>(
+    d: &mut [f64],
+    u: &[f64],
+    dim: (usize, usize),
+    h: f64,
+    solver: &S,
+) -> bool
+where
+    S: EikonalSolver,
+{
+    assert_eq!(dim.0 * dim.1, u.len());
+    assert_eq!(dim.0 * dim.1, d.len());
+
+    let seeded = level_set::init_dist_2d(d, u, dim, |p| EuclideanNorm.dual_norm(p));
+    solver.solve(d, dim);
+    signed_from_eikonal(d, u, h);
+    seeded > 0
+}
+
+/// Computes the signed distance from the zeros of the piecewise linear function given by the
+/// values of `u` on a regular 1D grid with spacing `h`.
+///
+/// The nodes at the ends of every interval in which `u` changes sign get their exact distance
+/// from the zero in it, and the distance is propagated in a sweep to the right and a sweep to the
+/// left. This is the 1D version of [`signed_distance_2d`](fn.signed_distance_2d.html) and returns
+/// `±std::f64::MAX` in the same way if there is no zero, and whether there is one.
+pub fn signed_distance_1d(d: &mut [f64], u: &[f64], h: f64) -> bool {
+    assert_eq!(u.len(), d.len());
+    signed_distance_1d_strided(d, u, u.len(), (1, 1), h)
+}
+
+/// Computes the signed distance like [`signed_distance_1d`](fn.signed_distance_1d.html) for `n`
+/// nodes, the node `k` at `d[k * strides.0]` and `u[k * strides.1]`.
+fn signed_distance_1d_strided(
+    d: &mut [f64],
+    u: &[f64],
+    n: usize,
+    strides: (usize, usize),
+    h: f64,
+) -> bool {
+    let (sd, su) = strides;
+    for k in 0..n {
+        d[k * sd] = std::f64::MAX;
+    }
+    for k in 1..n {
+        let (a, b) = (u[(k - 1) * su], u[k * su]);
+        if (a > 0. && b > 0.) || (a < 0. && b < 0.) {
+            continue;
+        }
+        let g = (b - a).abs();
+        let (da, db) = if g == 0. {
+            (0., 0.)
+        } else {
+            (a.abs() / g, b.abs() / g)
+        };
+        d[(k - 1) * sd] = min(d[(k - 1) * sd], da);
+        d[k * sd] = min(d[k * sd], db);
+    }
+    let crossed = (0..n).any(|k| d[k * sd] < std::f64::MAX);
+
+    for k in 1..n {
+        d[k * sd] = min(d[k * sd], d[(k - 1) * sd] + 1.);
+    }
+    for k in (1..n).rev() {
+        d[(k - 1) * sd] = min(d[(k - 1) * sd], d[k * sd] + 1.);
+    }
+
+    for k in 0..n {
+        d[k * sd] = signed_scaled(d[k * sd], u[k * su] < 0., h);
+    }
+    crossed
+}
+
+/// Computes the signed distance like [`signed_distance_2d`](fn.signed_distance_2d.html) and
+/// returns it in a new vector.
+pub fn signed_distance_2d_vec(u: &[f64], dim: (usize, usize), h: f64) -> Vec<f64> {
+    let mut d = vec![0.; u.len()];
+    signed_distance_2d(&mut d, u, dim, h);
+    d
+}
+
+/// Computes the signed distance like [`signed_distance_2d`](fn.signed_distance_2d.html), but
+/// returns an error instead of panicking on invalid input.
+///
+/// The grid must have at least 3 nodes along each axis unless it has at most one row or column,
+/// which `signed_distance_2d` solves in 1D. The lengths of `d` and `u` must match `dim`, `u` must
+/// be finite and `h` positive.
+pub fn try_signed_distance_2d(
+    d: &mut [f64],
+    u: &[f64],
+    dim: (usize, usize),
+    h: f64,
+) -> Result<(), SweepError> {
+    let preflight = error::Preflight::new(dim.0 * dim.1);
+    if dim.0 > 1 && dim.1 > 1 {
+        preflight.min_dim(&[dim.0, dim.1], 3)?;
+    }
+    preflight.finite("u", u)?.len("d", d.len())?.spacing(h)?;
+    signed_distance_2d(d, u, dim, h);
+    Ok(())
+}
+
+/// Computes the signed distance like [`signed_distance_2d`](fn.signed_distance_2d.html) for any
+/// floating point type `T`, for instance `f32` to halve the memory of large grids.
+///
+/// Returns `T::max_value()` if all `u` are nonnegative (`-T::max_value()` if all `u` are
+/// negative).
+pub fn signed_distance_2d_float<T>(d: &mut [T], u: &[T], dim: (usize, usize), h: T)
+where
+    T: num_traits::Float,
+{
+    level_set::init_dist_2d_float(d, u, dim, norm::euclidean_dual_norm_2d);
+    eikonal::fast_sweep_2d_float(d, dim, |d, v, _| norm::euclidean_inv_dual_norm_2d(d, v));
+    for (d, &u) in d.iter_mut().zip(u) {
+        if *d != T::max_value() {
+            *d = *d * h;
+        }
+        if u < T::zero() {
+            *d = -*d;
+        }
+    }
+}
+
+/// Computes the signed distance like [`signed_distance_2d`](fn.signed_distance_2d.html) for `u` and
+/// `d` in the _column-major_ order (Fortran order), as used by Fortran and MATLAB.
+///
+/// The node `(i, j)` of the grid of dimensions `dim` is `u[j * dim.0 + i]`. The triangulation of
+/// the grid is symmetric under transposition, so this is the row-major computation on the
+/// transposed grid, and nothing is copied. Returns whether the zero level set crosses the grid.
+pub fn signed_distance_colmajor(d: &mut [f64], u: &[f64], dim: (usize, usize), h: f64) -> bool {
+    signed_distance_2d(d, u, (dim.1, dim.0), h)
+}
+
+/// Computes the signed distance like [`signed_distance_2d`](fn.signed_distance_2d.html), with
+/// the grid dimensions taken from the shape of the arrays.
+///
+/// The arrays can be in the C or the Fortran order; the triangulation of the grid is symmetric
+/// under transposition, so a Fortran order array is processed as the C order array of the
+/// transposed grid. Otherwise, if the arrays are not contiguous or their orders differ, `u` is
+/// copied and the result is copied back to `d`.
+///
+/// Available with the `ndarray` feature.
+#[cfg(feature = "ndarray")]
+pub fn signed_distance_array(d: &mut ArrayViewMut2<f64>, u: &ArrayView2<f64>, h: f64) {
+    assert_eq!(d.dim(), u.dim(), "The arrays have different shapes");
+    let (ni, nj) = u.dim();
+    if d.is_standard_layout() && u.is_standard_layout() {
+        signed_distance_2d(
+            d.as_slice_mut().unwrap(),
+            u.as_slice().unwrap(),
+            (ni, nj),
+            h,
+        );
+    } else if d.t().is_standard_layout() && u.t().is_standard_layout() {
+        signed_distance_colmajor(
+            d.view_mut().reversed_axes().as_slice_mut().unwrap(),
+            u.t().as_slice().unwrap(),
+            (ni, nj),
+            h,
+        );
+    } else {
+        let v: Vec<f64> = u.iter().cloned().collect();
+        let mut e = vec![0.; ni * nj];
+        signed_distance_2d(&mut e, &v, (ni, nj), h);
+        d.assign(&ArrayView2::from_shape((ni, nj), &e).unwrap());
+    }
+}
+
+/// Computes the signed distance like [`signed_distance_2d`](fn.signed_distance_2d.html) on grids
+/// stored with a gap between the rows, for instance regions of a larger buffer.
+///
+/// `strides` are the distances in `d` and in `u` between the starts of consecutive rows; the
+/// nodes of a row are consecutive. The node `(i, j)` is `d[i * strides.0 + j]` and
+/// `u[i * strides.1 + j]`, and the values between the rows are neither read nor written.
+///
+/// The grid is initialized and swept in place with the strides, nothing is copied, and the result
+/// is the same as that of `signed_distance_2d` on the region, including the single row and column
+/// grids solved in 1D.
+pub fn signed_distance_strided(
+    d: &mut [f64],
+    u: &[f64],
+    dim: (usize, usize),
+    strides: (usize, usize),
+    h: f64,
+) {
+    let (ni, nj) = dim;
+    let (sd, su) = strides;
+    assert!(
+        sd >= nj && su >= nj,
+        "The strides {:?} are shorter than the rows",
+        strides
+    );
+    if ni == 0 {
+        return;
+    }
+    assert!(
+        d.len() >= (ni - 1) * sd + nj,
+        "d is too short for the strides"
+    );
+    assert!(
+        u.len() >= (ni - 1) * su + nj,
+        "u is too short for the strides"
+    );
+
+    if ni == 1 || nj <= 1 {
+        let (n, strides) = if ni == 1 {
+            (nj, (1, 1))
+        } else {
+            (ni * nj, strides)
+        };
+        signed_distance_1d_strided(d, u, n, strides, h);
+        return;
+    }
+
+    let mut dual_norm = |p: [f64; 2]| EuclideanNorm.dual_norm(p);
+    for i in 0..ni {
+        for d in &mut d[i * sd..i * sd + nj] {
+            *d = std::f64::MAX;
+        }
+        if i > 0 {
+            let (prev, cur) = (&u[(i - 1) * su..], &u[i * su..]);
+            for j in 1..nj {
+                let s = i * sd + j;
+                level_set::init_square_2d(
+                    d,
+                    [s - sd - 1, s - sd, s - 1, s],
+                    [prev[j - 1], prev[j], cur[j - 1], cur[j]],
+                    &mut dual_norm,
+                );
+            }
+        }
+    }
+
+    eikonal::fast_sweep_2d_strided(d, dim, sd, |d, v, s| EuclideanNorm.inv_dual_norm(d, v, s));
+
+    for i in 0..ni {
+        for j in 0..nj {
+            let s = i * sd + j;
+            d[s] = signed_scaled(d[s], u[i * su + j] < 0., h);
+        }
+    }
+}
+
+/// Computes the signed distance like [`signed_distance_2d`](fn.signed_distance_2d.html), but with
+/// the distance near the interface initialized on both diagonal splits of every square, see
+/// [`init_dist_2d_symmetric`](level_set/fn.init_dist_2d_symmetric.html).
+pub fn signed_distance_2d_symmetric(d: &mut [f64], u: &[f64], dim: (usize, usize), h: f64) {
+    assert_eq!(dim.0 * dim.1, u.len());
+    assert_eq!(dim.0 * dim.1, d.len());
+
+    level_set::init_dist_2d_symmetric(d, u, dim, |p| EuclideanNorm.dual_norm(p));
+    eikonal::fast_sweep_2d(d, dim, |d, v, s| EuclideanNorm.inv_dual_norm(d, v, s));
+
+    signed_from_eikonal(d, u, h);
+}
+
+/// Computes the (unsigned) distance from the _zero_ level set of `u` like
+/// [`signed_distance_2d`](fn.signed_distance_2d.html), but without giving it the sign of `u`.
+///
+/// This is the distance transform of the level set: `d` is nonnegative everywhere, and
+/// `std::f64::MAX` if there is no interface.
+pub fn unsigned_distance_2d(d: &mut [f64], u: &[f64], dim: (usize, usize), h: f64) {
+    assert_eq!(dim.0 * dim.1, u.len());
+    assert_eq!(dim.0 * dim.1, d.len());
+    if dim.0 <= 1 || dim.1 <= 1 {
+        signed_distance_1d(d, u, h);
+        for d in d.iter_mut() {
+            *d = d.abs();
+        }
+        return;
+    }
+    level_set::init_dist_2d(d, u, dim, |p| EuclideanNorm.dual_norm(p));
+    eikonal::fast_sweep_2d(d, dim, |d, v, s| EuclideanNorm.inv_dual_norm(d, v, s));
+    for d in d.iter_mut() {
+        *d = signed_scaled(*d, false, h);
+    }
+}
+
+/// Computes the signed distance from the `level` level set `{u = level}` of `u` like
+/// [`signed_distance_2d`](fn.signed_distance_2d.html), negative where `u < level`.
+///
+/// The result is that of `signed_distance_2d` on `u - level`, but the shifted values are computed
+/// row by row like in [`signed_distance_fn`](fn.signed_distance_fn.html) and never stored. A grid
+/// with a single row or column is passed to
+/// [`signed_distance_1d`](fn.signed_distance_1d.html) like in `signed_distance_2d`.
+pub fn signed_distance_level(d: &mut [f64], u: &[f64], dim: (usize, usize), h: f64, level: f64) {
+    assert_eq!(dim.0 * dim.1, u.len());
+    if dim.0 <= 1 || dim.1 <= 1 {
+        let shifted: Vec<f64> = u.iter().map(|u| u - level).collect();
+        signed_distance_1d(d, &shifted, h);
+        return;
+    }
+    signed_distance_fn(d, |i, j| u[i * dim.1 + j] - level, dim, h);
+}
+
+/// Computes the signed distances from several level sets `{u = c}` of `u`, one for every `c` in
+/// `levels`, like [`signed_distance_level`](fn.signed_distance_level.html).
+///
+/// The squares of the grid are traversed only once: the values of a square are read and its range
+/// is compared with every level, and only the levels that cross the square initialize it. The
+/// sweeps are then done for every level separately.
+pub fn signed_distance_levels(
+    u: &[f64],
+    dim: (usize, usize),
+    h: f64,
+    levels: &[f64],
+) -> Vec<Vec<f64>> {
+    let (ni, nj) = dim;
+    assert_eq!(ni * nj, u.len());
+
+    let mut ds = vec![vec![std::f64::MAX; ni * nj]; levels.len()];
+    let mut dual_norm = |p: [f64; 2]| EuclideanNorm.dual_norm(p);
+    for i in 1..ni {
+        for j in 1..nj {
+            let s = i * nj + j;
+            let v = [s - nj - 1, s - nj, s - 1, s];
+            let w = [u[v[0]], u[v[1]], u[v[2]], u[v[3]]];
+            let lo = min(min(w[0], w[1]), min(w[2], w[3]));
+            let hi = max(max(w[0], w[1]), max(w[2], w[3]));
+            for (d, &c) in ds.iter_mut().zip(levels) {
+                if lo <= c && c <= hi {
+                    let w = [w[0] - c, w[1] - c, w[2] - c, w[3] - c];
+                    level_set::init_square_2d(d, v, w, &mut dual_norm);
+                }
+            }
+        }
+    }
+
+    for (d, &c) in ds.iter_mut().zip(levels) {
+        eikonal::fast_sweep_2d(d, dim, |d, v, s| EuclideanNorm.inv_dual_norm(d, v, s));
+        for (d, &u) in d.iter_mut().zip(u) {
+            *d = signed_scaled(*d, u - c < 0., h);
+        }
+    }
+    ds
+}
+
+/// Computes the signed distance like [`signed_distance_2d`](fn.signed_distance_2d.html), with the
+/// level set function given by the closure `f(i, j)` instead of by its values on the grid.
+///
+/// `f` is evaluated exactly once at every node. Only two rows of its values are kept in memory at
+/// any time, together with the sign at every node, so the full level set function is never
+/// stored.
+pub fn signed_distance_fn<F>(d: &mut [f64], f: F, dim: (usize, usize), h: f64)
+where
+    F: Fn(usize, usize) -> f64,
+{
+    signed_distance_fn_with_scratch(d, f, dim, h, &mut Scratch::for_dim(dim));
+}
+
+/// Preallocated scratch buffers for
+/// [`signed_distance_with_scratch`](fn.signed_distance_with_scratch.html) and
+/// [`signed_distance_fn_with_scratch`](fn.signed_distance_fn_with_scratch.html).
+///
+/// Holds the sign mask of the level set function and two rows of its values. A `Scratch` can be
+/// reused for any number of calls on grids of the dimensions it was created for; the calls then
+/// do not allocate at all.
+#[derive(Clone, Debug)]
+pub struct Scratch {
+    dim: (usize, usize),
+    inside: Vec<bool>,
+    prev: Vec<f64>,
+    cur: Vec<f64>,
+}
+
+impl Scratch {
+    /// Allocates the scratch buffers for a grid of dimensions `dim`.
+    pub fn for_dim(dim: (usize, usize)) -> Scratch {
+        Scratch {
+            dim,
+            inside: vec![false; dim.0 * dim.1],
+            prev: vec![0.; dim.1],
+            cur: vec![0.; dim.1],
+        }
+    }
+}
+
+/// Computes the signed distance like [`signed_distance_2d`](fn.signed_distance_2d.html) without
+/// allocating, using the buffers in `scratch`.
+///
+/// The result is the same as that of `signed_distance_2d`. `scratch` must have been created for
+/// the dimensions `dim`.
+pub fn signed_distance_with_scratch(
+    d: &mut [f64],
+    u: &[f64],
+    dim: (usize, usize),
+    h: f64,
+    scratch: &mut Scratch,
+) {
+    assert_eq!(dim.0 * dim.1, u.len());
+    signed_distance_fn_with_scratch(d, |i, j| u[i * dim.1 + j], dim, h, scratch);
+}
+
+/// Computes the signed distance like [`signed_distance_fn`](fn.signed_distance_fn.html) without
+/// allocating, using the buffers in `scratch`.
+///
+/// `scratch` must have been created for the dimensions `dim`.
+pub fn signed_distance_fn_with_scratch<F>(
+    d: &mut [f64],
+    f: F,
+    dim: (usize, usize),
+    h: f64,
+    scratch: &mut Scratch,
+) where
+    F: Fn(usize, usize) -> f64,
+{
+    signed_distance_rows(d, |_, i, j| f(i, j), dim, h, scratch);
+}
+
+/// Computes the signed distance from the _zero_ level set of the function given by its values
+/// in `u` like [`signed_distance_2d`](fn.signed_distance_2d.html), and overwrites `u` with the
+/// result.
+///
+/// The level set function in `u` is destroyed. Only the sign of every node and two rows of the
+/// values are kept aside, so this saves the allocation of the second full array `d`.
+pub fn signed_distance_inplace(u: &mut [f64], dim: (usize, usize), h: f64) {
+    let mut scratch = Scratch::for_dim(dim);
+    signed_distance_rows(u, |u, i, j| u[i * dim.1 + j], dim, h, &mut scratch);
+}
+
+/// Computes the signed distance with the value of the level set function at `(i, j)` given by
+/// `f(d, i, j)`, where `d` is the output array.
+///
+/// The rows are read in order, each just before any value in it is written, so `f` can read the
+/// level set function from `d` itself.
+fn signed_distance_rows<F>(
+    d: &mut [f64],
+    mut f: F,
+    dim: (usize, usize),
+    h: f64,
+    scratch: &mut Scratch,
+) where
+    F: FnMut(&[f64], usize, usize) -> f64,
+{
+    let (ni, nj) = dim;
+    assert_eq!(ni * nj, d.len());
+    assert_eq!(
+        dim, scratch.dim,
+        "The scratch buffers are for the dimensions {:?}, not {:?}",
+        scratch.dim, dim
+    );
+
+    let mut dual_norm = |p: [f64; 2]| EuclideanNorm.dual_norm(p);
+    let Scratch {
+        ref mut inside,
+        ref mut prev,
+        ref mut cur,
+        ..
+    } = *scratch;
+    for i in 0..ni {
+        for j in 0..nj {
+            cur[j] = f(d, i, j);
+            inside[i * nj + j] = cur[j] < 0.;
+        }
+        for d in &mut d[i * nj..(i + 1) * nj] {
+            *d = std::f64::MAX;
+        }
+        if i > 0 {
+            for j in 1..nj {
+                let s = i * nj + j;
+                level_set::init_square_2d(
+                    d,
+                    [s - nj - 1, s - nj, s - 1, s],
+                    [prev[j - 1], prev[j], cur[j - 1], cur[j]],
+                    &mut dual_norm,
+                );
+            }
+        }
+        std::mem::swap(prev, cur);
+    }
+
+    eikonal::fast_sweep_2d(d, dim, |d, v, s| EuclideanNorm.inv_dual_norm(d, v, s));
+
+    for (d, &inside) in d.iter_mut().zip(inside.iter()) {
+        *d = signed_scaled(*d, inside, h);
+    }
 }
 
 /// Computes the signed distance from the _zero_ level set of the function given by the values of
@@ -117,64 +661,714 @@ pub fn signed_distance_2d(d: &mut [f64], u: &[f64], dim: (usize, usize), h: f64)
 ///
 /// `u` is assumed to be in the _row-major_ order (C order).
 ///
-/// Returns `std::f64::MAX` if all `u` are positive and `-std::f64::MAX` if all `u` are negative.
-pub fn signed_distance_3d(d: &mut [f64], u: &[f64], dim: (usize, usize, usize), h: f64) {
-    anisotropic_signed_distance_3d(d, u, dim, h, EuclideanNorm);
+/// Returns whether the zero level set crosses the grid. If it does not, all of `d` is set to
+/// `std::f64::MAX` if all `u` are positive and `-std::f64::MAX` if all `u` are negative.
+pub fn signed_distance_3d(d: &mut [f64], u: &[f64], dim: (usize, usize, usize), h: f64) -> bool {
+    anisotropic_signed_distance_3d(d, u, dim, h, EuclideanNorm)
+}
+
+/// Computes the signed distance like [`signed_distance_3d`](fn.signed_distance_3d.html) and
+/// returns it in a new vector.
+pub fn signed_distance_3d_vec(u: &[f64], dim: (usize, usize, usize), h: f64) -> Vec<f64> {
+    let mut d = vec![0.; u.len()];
+    signed_distance_3d(&mut d, u, dim, h);
+    d
+}
+
+/// Computes the signed distance like [`signed_distance_3d`](fn.signed_distance_3d.html), but
+/// returns an error instead of panicking on invalid input.
+///
+/// The grid must have at least 2 nodes along each axis, the lengths of `d` and `u` must match
+/// `dim`, `u` must be finite and `h` positive.
+pub fn try_signed_distance_3d(
+    d: &mut [f64],
+    u: &[f64],
+    dim: (usize, usize, usize),
+    h: f64,
+) -> Result<(), SweepError> {
+    error::Preflight::new(dim.0 * dim.1 * dim.2)
+        .min_dim(&[dim.0, dim.1, dim.2], 2)?
+        .finite("u", u)?
+        .len("d", d.len())?
+        .spacing(h)?;
+    signed_distance_3d(d, u, dim, h);
+    Ok(())
+}
+
+/// Computes the anisotropic signed distance function for a given norm.
+///
+/// The norm must be even (||p|| = ||-p||). Returns whether the zero level set crosses the grid.
+pub fn anisotropic_signed_distance_2d<N>(
+    d: &mut [f64],
+    u: &[f64],
+    dim: (usize, usize),
+    h: f64,
+    norm: N,
+) -> bool
+where
+    N: DualNorm<[f64; 2], f64>,
+{
+    assert_eq!(dim.0 * dim.1, u.len());
+    assert_eq!(dim.0 * dim.1, d.len());
+
+    let seeded = level_set::init_dist_2d(d, u, dim, |p| norm.dual_norm(p));
+    eikonal::fast_sweep_2d(d, dim, |d, v, s| norm.inv_dual_norm(d, v, s));
+
+    signed_from_eikonal(d, u, h);
+    seeded > 0
+}
+
+/// Computes the anisotropic signed distance function for a given norm.
+///
+/// The norm must be even (||p|| = ||-p||). Returns whether the zero level set crosses the grid.
+pub fn anisotropic_signed_distance_3d<N>(
+    d: &mut [f64],
+    u: &[f64],
+    dim: (usize, usize, usize),
+    h: f64,
+    norm: N,
+) -> bool
+where
+    N: DualNorm<[f64; 3], f64>,
+{
+    assert_eq!(dim.0 * dim.1 * dim.2, u.len());
+    assert_eq!(dim.0 * dim.1 * dim.2, d.len());
+
+    let seeded = level_set::init_dist_3d(d, u, dim, |p| norm.dual_norm(p));
+    eikonal::fast_sweep_3d(d, dim, |d, v, s| norm.inv_dual_norm(d, v, s));
+
+    signed_from_eikonal(d, u, h);
+    seeded > 0
+}
+
+/// Computes the signed distance on a grid obtained by an affine transformation of the integer
+/// lattice, such as a sheared or rotated grid.
+///
+/// The node `(i, j)` is at the world position `origin + transform · (i, j)ᵀ`, like the nodes of
+/// [`grid_from_scattered`](scattered/fn.grid_from_scattered.html), and the distance is measured
+/// in the world space. A translation of the grid does not change the distances, so `origin` only
+/// fixes the world positions of the samples in `u`. See
+/// [`AffineNorm`](norm/struct.AffineNorm.html) for the limitations on the shear.
+pub fn signed_distance_affine(
+    d: &mut [f64],
+    u: &[f64],
+    dim: (usize, usize),
+    transform: [[f64; 2]; 2],
+    origin: (f64, f64),
+) {
+    assert!(
+        origin.0.is_finite() && origin.1.is_finite(),
+        "The origin must be finite, was {:?}",
+        origin
+    );
+    anisotropic_signed_distance_2d(d, u, dim, 1., AffineNorm::new(transform));
+}
+
+/// Computes the signed distance like [`signed_distance_2d`](fn.signed_distance_2d.html) on a grid
+/// with the spacing `hx` along the first axis (`i`) and `hy` along the second (`j`), for instance
+/// an image with non-square pixels.
+///
+/// The node `(i, j)` is at `(i hx, j hy)` and both the initialization near the level set and the
+/// upwind updates of the sweeps measure the distance in these units. This is
+/// [`signed_distance_affine`](fn.signed_distance_affine.html) with a diagonal transform.
+pub fn signed_distance_aniso(d: &mut [f64], u: &[f64], dim: (usize, usize), spacing: (f64, f64)) {
+    let (hx, hy) = spacing;
+    assert!(
+        hx > 0. && hy > 0.,
+        "The spacing must be positive, was {:?}",
+        spacing
+    );
+    signed_distance_affine(d, u, dim, [[hx, 0.], [0., hy]], (0., 0.));
+}
+
+/// Computes the signed distance like [`signed_distance_2d`](fn.signed_distance_2d.html), with the
+/// grid spacing derived from the physical size of the domain.
+///
+/// `extent` is the distance between the first and the last node along each axis, so the spacing
+/// is `extent.0 / (ni - 1)` along `i` and `extent.1 / (nj - 1)` along `j`. If the two spacings
+/// differ, the grid is treated as a stretched grid (see
+/// [`signed_distance_aniso`](fn.signed_distance_aniso.html)) and the distance is still measured
+/// in the physical units.
+pub fn signed_distance_auto(d: &mut [f64], u: &[f64], dim: (usize, usize), extent: (f64, f64)) {
+    let (ni, nj) = dim;
+    assert!(
+        ni >= 2 && nj >= 2,
+        "The array dimensions must be at least (2, 2), were ({}, {})",
+        ni,
+        nj
+    );
+    assert!(
+        extent.0 > 0. && extent.1 > 0.,
+        "The extent must be positive, was {:?}",
+        extent
+    );
+
+    let hi = extent.0 / (ni - 1) as f64;
+    let hj = extent.1 / (nj - 1) as f64;
+    if (hi - hj).abs() <= 1e-12 * max(hi, hj) {
+        signed_distance_2d(d, u, dim, hi);
+    } else {
+        signed_distance_aniso(d, u, dim, (hi, hj));
+    }
+}
+
+/// Computes the signed distance like [`signed_distance_2d`](fn.signed_distance_2d.html), with
+/// the boundary conditions `bc` on the edges of the grid.
+///
+/// See the [`boundary` module](boundary/index.html) for the order of the edges. The values of
+/// `Dirichlet` conditions are distances, like `d`, in the same units as `h`.
+pub fn signed_distance_2d_bc(
+    d: &mut [f64],
+    u: &[f64],
+    dim: (usize, usize),
+    h: f64,
+    bc: [BoundaryCondition; 4],
+) {
+    assert_eq!(dim.0 * dim.1, u.len());
+    assert_eq!(dim.0 * dim.1, d.len());
+
+    if bc.contains(&BoundaryCondition::Extrapolate) {
+        // solve on the extended grid and copy the original part
+        let (v, ext, (oi, oj)) = boundary::extrapolate_2d(u, dim, bc);
+        let mut bc_ext = bc;
+        for c in bc_ext.iter_mut() {
+            if *c == BoundaryCondition::Extrapolate {
+                *c = BoundaryCondition::Open;
+            }
+        }
+        let mut e = vec![0.; ext.0 * ext.1];
+        signed_distance_2d_bc(&mut e, &v, ext, h, bc_ext);
+        for i in 0..dim.0 {
+            let row = (oi + i) * ext.1 + oj;
+            d[i * dim.1..][..dim.1].copy_from_slice(&e[row..][..dim.1]);
+        }
+        return;
+    }
+
+    let mut bc_grid = bc;
+    for c in bc_grid.iter_mut() {
+        if let BoundaryCondition::Dirichlet(ref mut g) = *c {
+            *g /= h;
+        }
+    }
+    boundary::init_dist_2d_bc(d, u, dim, bc_grid, |p| EuclideanNorm.dual_norm(p));
+    boundary::fast_sweep_2d_bc(d, dim, bc_grid, |d, v, s| {
+        EuclideanNorm.inv_dual_norm(d, v, s)
+    });
+
+    signed_from_eikonal(d, u, h);
+}
+
+/// Computes the signed distance function of the connected component of the region `{u < 0}`
+/// that contains the node `seed`, treating all other components as outside.
+///
+/// The component consists of the nodes with negative `u` that can be reached from `seed` through
+/// the edges of the triangles described in [`signed_distance_2d`](fn.signed_distance_2d.html),
+/// that is, horizontally, vertically and along the diagonal `(i, j)`–`(i + 1, j + 1)`. In the
+/// other components, and on their boundary, `u` is made positive before the distance is computed.
+///
+/// If `u` is nonnegative at `seed`, the selected component is empty and the result is
+/// `std::f64::MAX` everywhere (see `signed_distance_2d`).
+pub fn component_distance(
+    u: &[f64],
+    dim: (usize, usize),
+    h: f64,
+    seed: (usize, usize),
+) -> Vec<f64> {
+    let (ni, nj) = dim;
+    assert_eq!(ni * nj, u.len());
+    assert!(
+        seed.0 < ni && seed.1 < nj,
+        "The seed {:?} is outside of the grid of dimensions {:?}",
+        seed,
+        dim
+    );
+
+    // neighbors along the edges of the triangles
+    let neighbors = |i: usize, j: usize| {
+        let (i, j) = (i as isize, j as isize);
+        [(-1, 0), (1, 0), (0, -1), (0, 1), (-1, -1), (1, 1)]
+            .iter()
+            .map(move |&(di, dj)| (i + di, j + dj))
+            .filter(|&(a, b)| a >= 0 && b >= 0 && a < ni as isize && b < nj as isize)
+            .map(|(a, b)| (a as usize, b as usize))
+    };
+
+    let mut selected = vec![false; ni * nj];
+    let mut stack = vec![];
+    let s = seed.0 * nj + seed.1;
+    if u[s] < 0. {
+        selected[s] = true;
+        stack.push(seed);
+    }
+    while let Some((i, j)) = stack.pop() {
+        for (a, b) in neighbors(i, j) {
+            let t = a * nj + b;
+            if !selected[t] && u[t] < 0. {
+                selected[t] = true;
+                stack.push((a, b));
+            }
+        }
+    }
+
+    // zeros are kept only on the boundary of the selected component
+    let mut v = vec![0.; ni * nj];
+    for i in 0..ni {
+        for j in 0..nj {
+            let s = i * nj + j;
+            v[s] = if selected[s] || u[s] > 0. {
+                u[s]
+            } else if u[s] == 0. && neighbors(i, j).any(|(a, b)| selected[a * nj + b]) {
+                0.
+            } else {
+                max(-u[s], std::f64::MIN_POSITIVE)
+            };
+        }
+    }
+    let mut d = vec![0.; ni * nj];
+    signed_distance_2d(&mut d, &v, dim, h);
+    d
+}
+
+/// Computes the signed distance from the edges of the field `f`, that is, from the region where
+/// the magnitude of the gradient of `f` is at least `threshold`.
+///
+/// The gradient is computed by [`gradient_magnitude`](derivatives/fn.gradient_magnitude.html) and
+/// the level set `threshold - |∇f|` is passed to
+/// [`signed_distance_2d`](fn.signed_distance_2d.html), so the distance is negative on the edges
+/// and the interface is where `|∇f| = threshold`, with the same subcell accuracy as for any other
+/// level set.
+pub fn signed_distance_edges(
+    d: &mut [f64],
+    f: &[f64],
+    dim: (usize, usize),
+    h: f64,
+    threshold: f64,
+) {
+    let v: Vec<f64> = derivatives::gradient_magnitude(f, dim, h)
+        .iter()
+        .map(|g| threshold - g)
+        .collect();
+    signed_distance_2d(d, &v, dim, h);
+}
+
+/// Computes the signed distance like [`signed_distance_2d`](fn.signed_distance_2d.html) and passes
+/// the result to `f` in consecutive chunks of at most `chunk_len` nodes, together with the range
+/// of the indices of each chunk.
+///
+/// The chunks are passed in order and cover the whole grid, so a consumer can process or write
+/// them incrementally without keeping its own copy of the whole field.
+pub fn signed_distance_2d_chunks<F>(
+    u: &[f64],
+    dim: (usize, usize),
+    h: f64,
+    chunk_len: usize,
+    mut f: F,
+) where
+    F: FnMut(std::ops::Range<usize>, &[f64]),
+{
+    assert!(chunk_len > 0, "The chunk length must be positive");
+    let mut d = vec![0.; dim.0 * dim.1];
+    signed_distance_2d(&mut d, u, dim, h);
+    for (k, chunk) in d.chunks(chunk_len).enumerate() {
+        let start = k * chunk_len;
+        f(start..start + chunk.len(), chunk);
+    }
+}
+
+/// Computes the signed distance like [`signed_distance_2d`](fn.signed_distance_2d.html), with the
+/// sign of every node decided by the majority vote of the signs of `u` in its 3×3 neighborhood
+/// (clipped at the edges of the grid).
+///
+/// This removes isolated sign flips of a noisy level set function, which would otherwise show up
+/// as tiny inside or outside islands. On a tie the node keeps its own sign. Only the sign is
+/// voted on, the magnitude is the distance computed from `u`.
+pub fn signed_distance_2d_majority(d: &mut [f64], u: &[f64], dim: (usize, usize), h: f64) {
+    let (ni, nj) = dim;
+    signed_distance_2d(d, u, dim, h);
+
+    for i in 0..ni {
+        for j in 0..nj {
+            let mut votes = 0i32;
+            for a in i.saturating_sub(1)..std::cmp::min(i + 2, ni) {
+                for b in j.saturating_sub(1)..std::cmp::min(j + 2, nj) {
+                    votes += if u[a * nj + b] < 0. { -1 } else { 1 };
+                }
+            }
+            let s = i * nj + j;
+            let inside = match votes.signum() {
+                -1 => true,
+                1 => false,
+                _ => u[s] < 0.,
+            };
+            d[s] = if inside { -d[s].abs() } else { d[s].abs() };
+        }
+    }
+}
+
+/// Computes the signed distance from the boundary of the binary `mask` on a regular 2D grid with
+/// spacing `h`, negative at the nodes where `mask` is true.
+///
+/// The boundary passes halfway between the neighboring nodes that differ in the mask: the level
+/// set function is `-h/2` inside and `h/2` outside, and the distance is computed from it like in
+/// [`signed_distance_2d`](fn.signed_distance_2d.html). For the distance to the marked nodes
+/// themselves see the [`transform`](transform/index.html) module.
+pub fn distance_from_mask(d: &mut [f64], mask: &[bool], dim: (usize, usize), h: f64) {
+    assert_eq!(dim.0 * dim.1, mask.len());
+    let u: Vec<f64> = mask
+        .iter()
+        .map(|&inside| if inside { -0.5 * h } else { 0.5 * h })
+        .collect();
+    signed_distance_2d(d, &u, dim, h);
+}
+
+/// Computes the distance of every node from the boundary of its own region in the segmentation
+/// `labels` on a regular 2D grid with spacing `h`.
+///
+/// The regions are the sets of nodes with the same label. Like in
+/// [`distance_from_mask`](fn.distance_from_mask.html), the boundary passes halfway between the
+/// neighboring nodes with different labels: the squares with several labels initialize the
+/// distance of each of their nodes from the level set of the indicator of the node's own label,
+/// and the distance is then propagated by the sweeps. A single sweep serves all labels, since the
+/// nearest boundary of any region is always one of the node's own region.
+///
+/// The values are nonnegative. Every node is inside its own region, so the signed distance of the
+/// region of a node, negative inside like everywhere in this crate, is `-d` at the node; the
+/// signed distance of another region at the node is not computed. The nodes are `std::f64::MAX` if
+/// all labels are the same.
+pub fn signed_distance_labels(d: &mut [f64], labels: &[u32], dim: (usize, usize), h: f64) {
+    let (ni, nj) = dim;
+    assert_eq!(ni * nj, labels.len());
+    assert_eq!(ni * nj, d.len());
+
+    for d in &mut *d {
+        *d = std::f64::MAX;
+    }
+    let mut dual_norm = |p: [f64; 2]| EuclideanNorm.dual_norm(p);
+    for i in 1..ni {
+        for j in 1..nj {
+            let s = i * nj + j;
+            let v = [s - nj - 1, s - nj, s - 1, s];
+            let l = [labels[v[0]], labels[v[1]], labels[v[2]], labels[v[3]]];
+            if l.iter().all(|&k| k == l[0]) {
+                continue;
+            }
+            for c in 0..4 {
+                // the indicator of the label of the corner `c`
+                let mut w = [0.5; 4];
+                for m in 0..4 {
+                    if l[m] == l[c] {
+                        w[m] = -0.5;
+                    }
+                }
+                let mut e = [std::f64::MAX; 4];
+                level_set::init_square_2d(&mut e, [0, 1, 2, 3], w, &mut dual_norm);
+                d[v[c]] = min(d[v[c]], e[c]);
+            }
+        }
+    }
+
+    eikonal::fast_sweep_2d(d, dim, |d, v, s| EuclideanNorm.inv_dual_norm(d, v, s));
+    for d in d.iter_mut() {
+        *d = signed_scaled(*d, false, h);
+    }
+}
+
+/// Computes the distance from the nearest of the `points` on a regular 2D grid with spacing `h`.
+///
+/// The points are given in grid coordinates, the node `(i, j)` being at `(i, j)`, and must lie on
+/// the grid. The four nodes of the square containing a point get their exact distance from it,
+/// and the distance is then propagated by the sweeps like in
+/// [`signed_distance_2d`](fn.signed_distance_2d.html). The nodes are `std::f64::MAX` if there are
+/// no points.
+///
+/// The grid must have at least 3 nodes along each axis, like for
+/// [`eikonal::fast_sweep_2d`](eikonal/fn.fast_sweep_2d.html).
+pub fn distance_from_points(d: &mut [f64], points: &[(f64, f64)], dim: (usize, usize), h: f64) {
+    let (ni, nj) = dim;
+    assert_eq!(ni * nj, d.len());
+    assert!(ni >= 3 && nj >= 3, "The grid must be at least 3×3");
+
+    for d in &mut *d {
+        *d = std::f64::MAX;
+    }
+    for &(x, y) in points {
+        assert!(
+            x >= 0. && x <= (ni - 1) as f64 && y >= 0. && y <= (nj - 1) as f64,
+            "The point ({}, {}) is outside the grid",
+            x,
+            y
+        );
+        // the lower corner of the square, the last square at the far boundary
+        let i = std::cmp::min(x as usize, ni - 2);
+        let j = std::cmp::min(y as usize, nj - 2);
+        for &(a, b) in &[(i, j), (i, j + 1), (i + 1, j), (i + 1, j + 1)] {
+            let s = a * nj + b;
+            d[s] = min(d[s], (x - a as f64).hypot(y - b as f64));
+        }
+    }
+
+    eikonal::fast_sweep_2d(d, dim, |d, v, s| EuclideanNorm.inv_dual_norm(d, v, s));
+    for d in d.iter_mut() {
+        *d = signed_scaled(*d, false, h);
+    }
+}
+
+/// Decides whether the (unsigned) distance of the node `target` from the zero level set of `u`,
+/// computed like in [`signed_distance_2d`](fn.signed_distance_2d.html), is at most `threshold`.
+///
+/// The values only decrease during the sweeps, so the computation stops after the first sweep in
+/// which the value at `target` drops to `threshold`. Otherwise the sweeps in the four directions
+/// are repeated until nothing changes and the answer is `false`.
+pub fn within_distance_2d(
+    u: &[f64],
+    dim: (usize, usize),
+    h: f64,
+    target: (usize, usize),
+    threshold: f64,
+) -> bool {
+    let (ni, nj) = dim;
+    assert_eq!(ni * nj, u.len());
+    assert!(target.0 < ni && target.1 < nj);
+
+    let mut d = vec![0.; ni * nj];
+    level_set::init_dist_2d(&mut d, u, dim, |p| EuclideanNorm.dual_norm(p));
+    let t = target.0 * nj + target.1;
+    let limit = threshold / h;
+    if d[t] <= limit {
+        return true;
+    }
+
+    loop {
+        let mut change = 0.;
+        for &dir in &eikonal::SWEEP_DIRS_2D {
+            let c = eikonal::sweep_2d(
+                &mut d,
+                dim,
+                dir,
+                |_| false,
+                |d, s, ij, dir| {
+                    let v = eikonal::upwind_2d(d, dim, s, ij, dir, |_| false);
+                    EuclideanNorm.inv_dual_norm(d[s], v, [dir.0 as f64, dir.1 as f64])
+                },
+            );
+            if d[t] <= limit {
+                return true;
+            }
+            change = max(change, c);
+        }
+        if change == 0. {
+            return false;
+        }
+    }
+}
+
+/// Computes the signed distance like [`signed_distance_2d`](fn.signed_distance_2d.html) only near
+/// the interface, on the bounding box of the squares crossed by the zero level set of `u` grown by
+/// `padding` nodes on every side.
+///
+/// The box is clipped to the grid and grown to at least 3 × 3 nodes if necessary. The distance is
+/// computed on a copy of that part of `u` and written back into `d`; all other nodes get the
+/// sentinels `std::f64::MAX` (`-std::f64::MAX` where `u` is negative), like when there is no
+/// interface at all. Since the box contains the whole interface, the values inside it are the
+/// same as those of the computation on the full grid.
+pub fn signed_distance_cropped(
+    d: &mut [f64],
+    u: &[f64],
+    dim: (usize, usize),
+    h: f64,
+    padding: usize,
+) {
+    let (ni, nj) = dim;
+    assert_eq!(ni * nj, u.len());
+    assert_eq!(ni * nj, d.len());
+    assert!(
+        ni >= 3 && nj >= 3,
+        "The array dimensions must be at least (3, 3), were ({}, {})",
+        ni,
+        nj
+    );
+
+    for (d, &u) in d.iter_mut().zip(u) {
+        *d = if u < 0. {
+            -std::f64::MAX
+        } else {
+            std::f64::MAX
+        };
+    }
+
+    // the bounding box of the squares where `u` changes sign or vanishes
+    let (mut i0, mut i1, mut j0, mut j1) = (ni, 0, nj, 0);
+    for i in 1..ni {
+        for j in 1..nj {
+            let s = i * nj + j;
+            let v = [u[s - nj - 1], u[s - nj], u[s - 1], u[s]];
+            if v.iter().any(|&u| u <= 0.) && v.iter().any(|&u| u >= 0.) {
+                i0 = std::cmp::min(i0, i - 1);
+                i1 = std::cmp::max(i1, i + 1);
+                j0 = std::cmp::min(j0, j - 1);
+                j1 = std::cmp::max(j1, j + 1);
+            }
+        }
+    }
+    if i0 >= i1 {
+        return;
+    }
+
+    let grow = |lo: usize, hi: usize, n: usize| {
+        let lo = lo.saturating_sub(padding);
+        let hi = std::cmp::min(std::cmp::max(hi + padding, lo + 3), n);
+        (std::cmp::min(lo, hi - 3), hi)
+    };
+    let (i0, i1) = grow(i0, i1, ni);
+    let (j0, j1) = grow(j0, j1, nj);
+    let sub = (i1 - i0, j1 - j0);
+
+    let mut v = Vec::with_capacity(sub.0 * sub.1);
+    for i in i0..i1 {
+        v.extend_from_slice(&u[i * nj + j0..i * nj + j1]);
+    }
+    let mut e = vec![0.; sub.0 * sub.1];
+    signed_distance_2d(&mut e, &v, sub, h);
+    for i in i0..i1 {
+        d[i * nj + j0..i * nj + j1].copy_from_slice(&e[(i - i0) * sub.1..][..sub.1]);
+    }
 }
 
-/// Computes the anisotropic signed distance function for a given norm.
+/// Computes the signed distance like [`signed_distance_2d`](fn.signed_distance_2d.html), restricted
+/// to the nodes inside the polygon `clip`; the other nodes are set to `NaN`.
 ///
-/// The norm must be even (||p|| = ||-p||).
-pub fn anisotropic_signed_distance_2d<N>(
+/// The node `(i, j)` is at `(i h, j h)` and the vertices of `clip` are given in the same
+/// coordinates. A node is inside by the even-odd rule, so the polygon does not need to be simple
+/// or oriented; the nodes exactly on its boundary may fall on either side. Only the squares with
+/// all four vertices inside are used to initialize the distance near the interface and the sweeps
+/// visit only the nodes inside, so the distance is measured along paths within the clip region.
+/// The sweeps are repeated until nothing changes, since the region need not be convex.
+pub fn signed_distance_clipped(
     d: &mut [f64],
     u: &[f64],
     dim: (usize, usize),
     h: f64,
-    norm: N,
-) where
-    N: DualNorm<[f64; 2], f64>,
-{
-    assert_eq!(dim.0 * dim.1, u.len());
-    assert_eq!(dim.0 * dim.1, d.len());
+    clip: &[(f64, f64)],
+) {
+    let (ni, nj) = dim;
+    assert_eq!(ni * nj, u.len());
+    assert_eq!(ni * nj, d.len());
+    assert!(
+        clip.len() >= 3,
+        "The clip polygon needs at least 3 vertices"
+    );
 
-    level_set::init_dist_2d(d, u, dim, |p| norm.dual_norm(p));
-    eikonal::fast_sweep_2d(d, dim, |d, v, s| norm.inv_dual_norm(d, v, s));
+    let mut inside = vec![false; ni * nj];
+    // the bounding box of the inside nodes
+    let (mut i0, mut i1, mut j0, mut j1) = (ni, 0, nj, 0);
+    for i in 0..ni {
+        for j in 0..nj {
+            if in_polygon(clip, (i as f64 * h, j as f64 * h)) {
+                inside[i * nj + j] = true;
+                i0 = std::cmp::min(i0, i);
+                i1 = std::cmp::max(i1, i + 1);
+                j0 = std::cmp::min(j0, j);
+                j1 = std::cmp::max(j1, j + 1);
+            }
+        }
+    }
 
-    // compute the signed distance function from the solution of the eikonal equation
-    for i in 0..d.len() {
-        if u[i] < 0. {
-            d[i] = -d[i] * h;
-        } else {
-            d[i] *= h;
+    for d in &mut *d {
+        *d = std::f64::MAX;
+    }
+    let mut dual_norm = |p: [f64; 2]| EuclideanNorm.dual_norm(p);
+    for i in i0 + 1..i1 {
+        for j in j0 + 1..j1 {
+            let s = i * nj + j;
+            let v = [s - nj - 1, s - nj, s - 1, s];
+            if v.iter().all(|&t| inside[t]) {
+                let w = [u[v[0]], u[v[1]], u[v[2]], u[v[3]]];
+                level_set::init_square_2d(d, v, w, &mut dual_norm);
+            }
+        }
+    }
+
+    let outside: Vec<bool> = inside.iter().map(|&inside| !inside).collect();
+    eikonal::fast_sweep_dist_2d_masked(d, &outside, dim);
+
+    signed_from_eikonal(d, u, h);
+    for (d, &inside) in d.iter_mut().zip(&inside) {
+        if !inside {
+            *d = std::f64::NAN;
         }
     }
 }
 
-/// Computes the anisotropic signed distance function for a given norm.
+/// Whether the point `p` is inside the polygon by the even-odd rule.
+fn in_polygon(polygon: &[(f64, f64)], p: (f64, f64)) -> bool {
+    let (x, y) = p;
+    let mut inside = false;
+    let mut prev = polygon[polygon.len() - 1];
+    for &cur in polygon {
+        if (cur.1 > y) != (prev.1 > y)
+            && x < prev.0 + (y - prev.1) * (cur.0 - prev.0) / (cur.1 - prev.1)
+        {
+            inside = !inside;
+        }
+        prev = cur;
+    }
+    inside
+}
+
+/// Computes the signed distance like [`signed_distance_2d`](fn.signed_distance_2d.html) for every
+/// channel `u[c]` of a multi-valued level set function and combines the channels at every node
+/// with `reduce`.
 ///
-/// The norm must be even (||p|| = ||-p||).
-pub fn anisotropic_signed_distance_3d<N>(
+/// `reduce` receives the signed distances of all channels at a node, in the order of `u`. For
+/// instance, the minimum gives the distance function of the union of the regions `{u[c] < 0}`.
+pub fn signed_distance_multichannel<F>(
     d: &mut [f64],
-    u: &[f64],
-    dim: (usize, usize, usize),
+    u: &[Vec<f64>],
+    dim: (usize, usize),
     h: f64,
-    norm: N,
+    reduce: F,
 ) where
-    N: DualNorm<[f64; 3], f64>,
+    F: Fn(&[f64]) -> f64,
 {
-    assert_eq!(dim.0 * dim.1 * dim.2, u.len());
-    assert_eq!(dim.0 * dim.1 * dim.2, d.len());
+    let n = dim.0 * dim.1;
+    assert_eq!(n, d.len());
 
-    level_set::init_dist_3d(d, u, dim, |p| norm.dual_norm(p));
-    eikonal::fast_sweep_3d(d, dim, |d, v, s| norm.inv_dual_norm(d, v, s));
+    let channels: Vec<Vec<f64>> = u
+        .iter()
+        .map(|u| {
+            let mut d = vec![0.; n];
+            signed_distance_2d(&mut d, u, dim, h);
+            d
+        })
+        .collect();
+    let mut values = vec![0.; u.len()];
+    for (s, d) in d.iter_mut().enumerate() {
+        for (v, c) in values.iter_mut().zip(&channels) {
+            *v = c[s];
+        }
+        *d = reduce(&values);
+    }
+}
 
-    // compute the signed distance function from the solution of the eikonal equation
+/// Computes the signed distance function from the solution `d` of the eikonal equation in place.
+fn signed_from_eikonal(d: &mut [f64], u: &[f64], h: f64) {
     for i in 0..d.len() {
-        if u[i] < 0. {
-            d[i] = -d[i] * h;
-        } else {
-            d[i] *= h;
-        }
+        d[i] = signed_scaled(d[i], u[i] < 0., h);
+    }
+}
+
+/// Scales the solution `d` of the eikonal equation at one node by `h` and gives it the sign.
+///
+/// The value `std::f64::MAX` of the nodes that no information reached (there is no interface) is
+/// kept as the sentinel `±std::f64::MAX` instead of being scaled.
+#[inline(always)]
+fn signed_scaled(d: f64, inside: bool, h: f64) -> f64 {
+    let d = if d == std::f64::MAX { d } else { d * h };
+    if inside {
+        -d
+    } else {
+        d
     }
 }
 
@@ -318,6 +1512,334 @@ mod tests {
         quickcheck(prop as fn(f64) -> bool);
     }
 
+    #[test]
+    fn closure_matches_slice() {
+        let (ni, nj) = (23, 17);
+        let h = 1. / (ni - 1) as f64;
+        let circle = |i: usize, j: usize| {
+            let (x, y) = (i as f64 * h - 0.45, j as f64 * h - 0.4);
+            x.hypot(y) - 0.3
+        };
+        let mut u = vec![0.; ni * nj];
+        for i in 0..ni {
+            for j in 0..nj {
+                u[i * nj + j] = circle(i, j);
+            }
+        }
+        let mut d = vec![0.; ni * nj];
+        signed_distance_2d(&mut d, &u, (ni, nj), h);
+        let mut d_fn = vec![0.; ni * nj];
+        signed_distance_fn(&mut d_fn, circle, (ni, nj), h);
+        assert_eq!(d, d_fn);
+    }
+
+    #[test]
+    fn it_works_on_sheared_grid() {
+        let (ni, nj) = (17, 15);
+        let h = 0.1;
+        let a = [[h, 0.3 * h], [0., h]];
+        let o = (-0.4, 0.25);
+        let det = a[0][0] * a[1][1] - a[0][1] * a[1][0];
+        // lines whose normals are (in the world space) along the lattice directions e₁, e₂, e₁ ± e₂
+        for &(gi, gj) in &[(1f64, 0f64), (0., 1.), (1., 1.), (1., -1.)] {
+            let (nx, ny) = (a[0][0] * gi + a[0][1] * gj, a[1][0] * gi + a[1][1] * gj);
+            let (nx, ny) = (nx / nx.hypot(ny), ny / nx.hypot(ny));
+            let mut u = vec![0.; ni * nj];
+            let mut interior = vec![false; ni * nj];
+            for i in 0..ni {
+                for j in 0..nj {
+                    let s = i * nj + j;
+                    let (i, j) = (i as f64, j as f64);
+                    let x = o.0 + a[0][0] * i + a[0][1] * j;
+                    let y = o.1 + a[1][0] * i + a[1][1] * j;
+                    // world space signed distance to a line
+                    u[s] = nx * (x - o.0 - 1.) + ny * (y - o.1 - 0.7);
+                    // is the closest point on the line inside the grid?
+                    let (px, py) = (x - u[s] * nx - o.0, y - u[s] * ny - o.1);
+                    let pi = (a[1][1] * px - a[0][1] * py) / det;
+                    let pj = (-a[1][0] * px + a[0][0] * py) / det;
+                    interior[s] = pi > -1e-9
+                        && pi < (ni - 1) as f64 + 1e-9
+                        && pj > -1e-9
+                        && pj < (nj - 1) as f64 + 1e-9;
+                }
+            }
+            let mut d = vec![0.; ni * nj];
+            signed_distance_affine(&mut d, &u, (ni, nj), a, o);
+            for s in 0..ni * nj {
+                if interior[s] {
+                    assert!(
+                        (d[s] - u[s]).abs() < 1e-6,
+                        "normal {:?}: {} != {}",
+                        (gi, gj),
+                        d[s],
+                        u[s]
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn component_of_two_disks() {
+        let n = 41;
+        let h = 1. / (n - 1) as f64;
+        let disks = [(0.3, 0.3, 0.15), (0.7, 0.65, 0.2)];
+        let mut u = vec![0.; n * n];
+        let mut first = vec![0.; n * n];
+        for i in 0..n {
+            for j in 0..n {
+                let (x, y) = (i as f64 * h, j as f64 * h);
+                let r: Vec<f64> = disks
+                    .iter()
+                    .map(|&(cx, cy, r)| (x - cx).hypot(y - cy) - r)
+                    .collect();
+                u[i * n + j] = r[0].min(r[1]);
+                first[i * n + j] = r[0];
+            }
+        }
+
+        let d = component_distance(&u, (n, n), h, (12, 12));
+        let mut expected = vec![0.; n * n];
+        signed_distance_2d(&mut expected, &first, (n, n), h);
+        for s in 0..n * n {
+            assert!(
+                (d[s] - expected[s]).abs() < 1e-12,
+                "{} != {}",
+                d[s],
+                expected[s]
+            );
+        }
+        // the other disk reads as outside
+        assert!(d[28 * n + 26] > 0.1);
+
+        // no component is selected outside of the disks
+        let d = component_distance(&u, (n, n), h, (0, 0));
+        assert!(d.iter().all(|&d| d == std::f64::MAX));
+    }
+
+    #[test]
+    fn majority_removes_isolated_flips() {
+        let n = 33;
+        let h = 1. / (n - 1) as f64;
+        let mut u = vec![0.; n * n];
+        let mut clean = vec![0.; n * n];
+        let mut seed = 7u64;
+        for i in 0..n {
+            for j in 0..n {
+                let (x, y) = (i as f64 * h - 0.5, j as f64 * h - 0.5);
+                let v = x.hypot(y) - 0.3;
+                seed = seed
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                // flip the sign of about 5% of the nodes
+                let flip = (seed >> 33) % 20 < 1;
+                clean[i * n + j] = v;
+                u[i * n + j] = if flip { -v } else { v };
+            }
+        }
+
+        let mut plain = vec![0.; n * n];
+        let mut voted = vec![0.; n * n];
+        signed_distance_2d(&mut plain, &u, (n, n), h);
+        signed_distance_2d_majority(&mut voted, &u, (n, n), h);
+
+        let wrong = |d: &[f64]| {
+            (0..n * n)
+                .filter(|&s| clean[s].abs() > 2. * h && (d[s] < 0.) != (clean[s] < 0.))
+                .count()
+        };
+        assert!(wrong(&plain) > 20);
+        assert_eq!(wrong(&voted), 0);
+        for s in 0..n * n {
+            assert_eq!(voted[s].abs(), plain[s].abs());
+        }
+    }
+
+    #[test]
+    fn early_out_matches_full_computation() {
+        let n = 41;
+        let h = 1. / (n - 1) as f64;
+        let mut u = vec![0.; n * n];
+        for i in 0..n {
+            for j in 0..n {
+                let (x, y) = (i as f64 * h - 0.3, j as f64 * h - 0.6);
+                u[i * n + j] = x.hypot(y) - 0.15;
+            }
+        }
+        let mut d = vec![0.; n * n];
+        signed_distance_2d(&mut d, &u, (n, n), h);
+        for &target in &[(0, 0), (12, 24), (40, 40), (40, 0), (20, 20)] {
+            let dist = d[target.0 * n + target.1].abs();
+            for &threshold in &[0.05, 0.2, 0.4, 0.6, 1.] {
+                assert_eq!(
+                    within_distance_2d(&u, (n, n), h, target, threshold),
+                    dist <= threshold,
+                    "{:?} {}",
+                    target,
+                    threshold
+                );
+            }
+        }
+        // no interface
+        assert!(!within_distance_2d(&[1.; 9], (3, 3), h, (1, 1), 1e10));
+    }
+
+    #[test]
+    fn multichannel_min() {
+        let n = 25;
+        let h = 1. / (n - 1) as f64;
+        let mut u = vec![vec![0.; n * n], vec![0.; n * n]];
+        for i in 0..n {
+            for j in 0..n {
+                let (x, y) = (i as f64 * h, j as f64 * h);
+                u[0][i * n + j] = (x - 0.3).hypot(y - 0.3) - 0.2;
+                u[1][i * n + j] = x + y - 1.4;
+            }
+        }
+        let mut d = vec![0.; n * n];
+        signed_distance_multichannel(&mut d, &u, (n, n), h, |v| {
+            v.iter().cloned().fold(std::f64::MAX, f64::min)
+        });
+
+        let mut a = vec![0.; n * n];
+        let mut b = vec![0.; n * n];
+        signed_distance_2d(&mut a, &u[0], (n, n), h);
+        signed_distance_2d(&mut b, &u[1], (n, n), h);
+        for s in 0..n * n {
+            assert_eq!(d[s], a[s].min(b[s]));
+        }
+    }
+
+    #[test]
+    fn scratch_is_reused() {
+        let (ni, nj) = (19, 23);
+        let h = 0.05;
+        let mut scratch = Scratch::for_dim((ni, nj));
+        let ptr = scratch.inside.as_ptr();
+        for k in 0..3 {
+            let mut u = vec![0.; ni * nj];
+            for i in 0..ni {
+                for j in 0..nj {
+                    let (x, y) = (i as f64 * h - 0.4, j as f64 * h - 0.5 - 0.05 * k as f64);
+                    u[i * nj + j] = x.hypot(y) - 0.3;
+                }
+            }
+            let mut d = vec![0.; ni * nj];
+            let mut e = vec![0.; ni * nj];
+            signed_distance_with_scratch(&mut d, &u, (ni, nj), h, &mut scratch);
+            signed_distance_2d(&mut e, &u, (ni, nj), h);
+            assert_eq!(d, e);
+        }
+        assert_eq!(scratch.inside.as_ptr(), ptr);
+    }
+
+    #[test]
+    fn inplace_matches_separate_output() {
+        let (ni, nj) = (21, 17);
+        let h = 0.05;
+        let mut u = vec![0.; ni * nj];
+        for i in 0..ni {
+            for j in 0..nj {
+                let (x, y) = (i as f64 * h - 0.5, j as f64 * h - 0.4);
+                u[i * nj + j] = x.hypot(y) - 0.3;
+            }
+        }
+        let mut d = vec![0.; ni * nj];
+        signed_distance_2d(&mut d, &u, (ni, nj), h);
+        signed_distance_inplace(&mut u, (ni, nj), h);
+        assert_eq!(u, d);
+    }
+
+    #[test]
+    fn chunks_reassemble() {
+        let (ni, nj) = (13, 17);
+        let h = 0.1;
+        let mut u = vec![0.; ni * nj];
+        for i in 0..ni {
+            for j in 0..nj {
+                let (x, y) = (i as f64 * h - 0.6, j as f64 * h - 0.7);
+                u[i * nj + j] = x.hypot(y) - 0.4;
+            }
+        }
+        let mut d = vec![0.; ni * nj];
+        signed_distance_2d(&mut d, &u, (ni, nj), h);
+
+        let mut r = vec![];
+        let mut ends = vec![];
+        signed_distance_2d_chunks(&u, (ni, nj), h, 50, |range, chunk| {
+            assert_eq!(range.start, r.len());
+            assert_eq!(range.len(), chunk.len());
+            r.extend_from_slice(chunk);
+            ends.push(range.end);
+        });
+        assert_eq!(r, d);
+        assert_eq!(ends, [50, 100, 150, 200, 221]);
+    }
+
+    #[test]
+    fn distance_from_edges() {
+        // a smoothed step across x = 0.5; the edge is where |f'| >= 1 / (2 w), that is,
+        // |x - 0.5| <= w acosh(√2)
+        let n = 65;
+        let h = 1. / (n - 1) as f64;
+        let w = 0.05;
+        let half_width = w * 2f64.sqrt().acosh();
+        let mut f = vec![0.; n * n];
+        for i in 0..n {
+            for j in 0..n {
+                f[i * n + j] = ((i as f64 * h - 0.5) / w).tanh();
+            }
+        }
+        let mut d = vec![0.; n * n];
+        signed_distance_edges(&mut d, &f, (n, n), h, 0.5 / w);
+        for i in 1..n - 1 {
+            let x = i as f64 * h;
+            for j in 0..n {
+                let e = (x - 0.5).abs() - half_width;
+                assert!(
+                    (d[i * n + j] - e).abs() < 0.2 * h,
+                    "{} != {}",
+                    d[i * n + j],
+                    e
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn auto_spacing_from_extent() {
+        // the domain [0, 2] × [0, 2] with 21 × 21 nodes has spacing 0.1
+        let n = 21;
+        let mut u = vec![0.; n * n];
+        for i in 0..n {
+            for j in 0..n {
+                let (x, y) = (i as f64 * 0.1 - 1., j as f64 * 0.1 - 0.9);
+                u[i * n + j] = x.hypot(y) - 0.55;
+            }
+        }
+        let mut d = vec![0.; n * n];
+        let mut d_h = vec![0.; n * n];
+        signed_distance_auto(&mut d, &u, (n, n), (2., 2.));
+        signed_distance_2d(&mut d_h, &u, (n, n), 0.1);
+        assert_eq!(d, d_h);
+
+        // stretched grid [0, 2] × [0, 1]: the distance to the lines x = 0.93 and y = 0.37 is in
+        // the physical units
+        for &(gx, gy, c) in &[(1., 0., 0.93), (0., 1., 0.37)] {
+            for i in 0..n {
+                for j in 0..n {
+                    let (x, y) = (i as f64 * 0.1, j as f64 * 0.05);
+                    u[i * n + j] = gx * x + gy * y - c;
+                }
+            }
+            signed_distance_auto(&mut d, &u, (n, n), (2., 1.));
+            for s in 0..n * n {
+                assert!((d[s] - u[s]).abs() < 1e-12, "{} != {}", d[s], u[s]);
+            }
+        }
+    }
+
     fn check_plane(
         gx: f64,
         gy: f64,
@@ -401,4 +1923,552 @@ mod tests {
         quickcheck(prop as fn(f64) -> bool);
     }
 
+    #[test]
+    fn clipped_to_square() {
+        let n = 41;
+        let h = 1. / (n - 1) as f64;
+        let mut u = vec![0.; n * n];
+        for i in 0..n {
+            for j in 0..n {
+                let (x, y) = (i as f64 * h - 0.5, j as f64 * h - 0.5);
+                u[i * n + j] = x.hypot(y) - 0.2;
+            }
+        }
+        let mut full = vec![0.; n * n];
+        signed_distance_2d(&mut full, &u, (n, n), h);
+
+        let (x0, x1, y0, y1) = (0.21, 0.86, 0.14, 0.81);
+        let clip = [(x0, y0), (x1, y0), (x1, y1), (x0, y1)];
+        let mut d = vec![0.; n * n];
+        signed_distance_clipped(&mut d, &u, (n, n), h, &clip);
+        let mut count = 0;
+        for i in 0..n {
+            for j in 0..n {
+                let s = i * n + j;
+                let (x, y) = (i as f64 * h, j as f64 * h);
+                if x > x0 && x < x1 && y > y0 && y < y1 {
+                    assert!((d[s] - full[s]).abs() < 1e-9, "{} != {}", d[s], full[s]);
+                    count += 1;
+                } else {
+                    assert!(d[s].is_nan(), "{:?} is {}", (i, j), d[s]);
+                }
+            }
+        }
+        assert_eq!(count, 26 * 27);
+    }
+
+    #[test]
+    fn cropped_matches_full() {
+        let n = 61;
+        let h = 1. / (n - 1) as f64;
+        let mut u = vec![0.; n * n];
+        for i in 0..n {
+            for j in 0..n {
+                let (x, y) = (i as f64 * h - 0.4, j as f64 * h - 0.55);
+                u[i * n + j] = x.hypot(y) - 0.15;
+            }
+        }
+        let mut full = vec![0.; n * n];
+        signed_distance_2d(&mut full, &u, (n, n), h);
+
+        let mut d = vec![0.; n * n];
+        signed_distance_cropped(&mut d, &u, (n, n), h, 4);
+        let (mut near, mut far) = (0, 0);
+        for s in 0..n * n {
+            if d[s].abs() == std::f64::MAX {
+                assert_eq!(d[s] < 0., u[s] < 0.);
+                assert!(full[s].abs() > 4. * h, "{}", full[s]);
+                far += 1;
+            } else {
+                assert!((d[s] - full[s]).abs() < 1e-9, "{} != {}", d[s], full[s]);
+                near += 1;
+            }
+            if full[s].abs() > 0.5 {
+                assert_eq!(d[s], std::f64::MAX);
+            }
+        }
+        assert!(near > 0 && far > 0);
+
+        // no interface
+        let mut d = [0.; 9];
+        signed_distance_cropped(&mut d, &[-1.; 9], (3, 3), h, 2);
+        assert!(d.iter().all(|&d| d == -std::f64::MAX));
+    }
+
+    #[test]
+    fn vec_matches_preallocated() {
+        let u: Vec<f64> = (0..20).map(|s| (s / 5) as f64 - 1.5).collect();
+        let mut d = vec![0.; 20];
+        signed_distance_2d(&mut d, &u, (4, 5), 0.5);
+        assert_eq!(signed_distance_2d_vec(&u, (4, 5), 0.5), d);
+        let mut d = vec![0.; 20];
+        signed_distance_3d(&mut d, &u, (2, 5, 2), 0.5);
+        assert_eq!(signed_distance_3d_vec(&u, (2, 5, 2), 0.5), d);
+    }
+
+    #[test]
+    fn it_works_for_sphere() {
+        let (ni, nj, nk) = (21, 25, 23);
+        let h = 0.05;
+        let c = (0.48, 0.61, 0.55);
+        let r = 0.3;
+        let mut u = vec![0.; ni * nj * nk];
+        for i in 0..ni {
+            for j in 0..nj {
+                for k in 0..nk {
+                    let (x, y, z) = (i as f64 * h - c.0, j as f64 * h - c.1, k as f64 * h - c.2);
+                    u[(i * nj + j) * nk + k] = (x * x + y * y + z * z).sqrt() - r;
+                }
+            }
+        }
+        let mut d = vec![0.; ni * nj * nk];
+        signed_distance_3d(&mut d, &u, (ni, nj, nk), h);
+        for s in 0..ni * nj * nk {
+            assert_eq!(d[s] < 0., u[s] < 0.);
+            // first order accuracy
+            assert!((d[s] - u[s]).abs() < 2. * h, "{} != {}", d[s], u[s]);
+        }
+
+        // no interface
+        for &v in &[1., -1.] {
+            let mut d = [0.; 27];
+            signed_distance_3d(&mut d, &[v; 27], (3, 3, 3), 0.1);
+            assert!(d.iter().all(|&d| d == v * std::f64::MAX));
+        }
+    }
+
+    #[test]
+    fn aniso_spacing_tilted_line() {
+        let (ni, nj) = (17, 31);
+        let (hx, hy) = (0.1, 0.04);
+        let (nx, ny) = (0.6, 0.8);
+        let mut u = vec![0.; ni * nj];
+        let mut interior = vec![false; ni * nj];
+        for i in 0..ni {
+            for j in 0..nj {
+                let s = i * nj + j;
+                let (x, y) = (i as f64 * hx, j as f64 * hy);
+                u[s] = nx * (x - 0.8) + ny * (y - 0.55);
+                // is the closest point on the line inside the grid? The nodes on the edges get
+                // their values along the edge from the line cut off at the corner.
+                let (px, py) = (x - u[s] * nx, y - u[s] * ny);
+                interior[s] = i > 0
+                    && i < ni - 1
+                    && j > 0
+                    && j < nj - 1
+                    && px > -1e-9
+                    && px < (ni - 1) as f64 * hx + 1e-9
+                    && py > -1e-9
+                    && py < (nj - 1) as f64 * hy + 1e-9;
+            }
+        }
+        let mut d = vec![0.; ni * nj];
+        signed_distance_aniso(&mut d, &u, (ni, nj), (hx, hy));
+        let mut iso = vec![0.; ni * nj];
+        signed_distance_2d(&mut iso, &u, (ni, nj), hx);
+        let mut count = 0;
+        for s in 0..ni * nj {
+            if interior[s] {
+                assert!((d[s] - u[s]).abs() < 1e-6, "{} != {}", d[s], u[s]);
+                count += 1;
+            }
+        }
+        assert!(count > ni * nj / 2);
+        // a single spacing gets the distance wrong
+        assert!((0..ni * nj).any(|s| interior[s] && (iso[s] - u[s]).abs() > 0.1));
+    }
+
+    #[test]
+    #[cfg(feature = "ndarray")]
+    fn array_orders_match_slice() {
+        let (ni, nj) = (17, 23);
+        let h = 0.05;
+        let f = |i: usize, j: usize| (i as f64 * h - 0.4).hypot(j as f64 * h - 0.5) - 0.3;
+        let u = Array::from_shape_fn((ni, nj), |(i, j)| f(i, j));
+        let mut expected = vec![0.; ni * nj];
+        signed_distance_2d(&mut expected, u.as_slice().unwrap(), (ni, nj), h);
+        let expected = Array::from_shape_vec((ni, nj), expected).unwrap();
+
+        let mut d = Array::zeros((ni, nj));
+        signed_distance_array(&mut d.view_mut(), &u.view(), h);
+        assert_eq!(d, expected);
+
+        let uf = Array::from_shape_fn((ni, nj).f(), |(i, j)| f(i, j));
+        let mut df = Array::zeros((ni, nj).f());
+        signed_distance_array(&mut df.view_mut(), &uf.view(), h);
+        assert!(df.all_close(&expected, 1e-12));
+
+        // mixed orders are copied
+        let mut d = Array::zeros((ni, nj));
+        signed_distance_array(&mut d.view_mut(), &uf.view(), h);
+        assert_eq!(d, expected);
+
+        // a strided view
+        let big = Array::from_shape_fn((ni, 2 * nj), |(i, j)| f(i, j / 2));
+        let mut d = Array::zeros((ni, nj));
+        signed_distance_array(&mut d.view_mut(), &big.slice(s![.., ..;2]), h);
+        assert_eq!(d, expected);
+    }
+
+    #[test]
+    fn strided_region_of_interest() {
+        let (ni, nj) = (17, 23);
+        let h = 0.05;
+        let f = |i: usize, j: usize| (i as f64 * h - 0.4).hypot(j as f64 * h - 0.5) - 0.3;
+        let mut u = vec![0.; ni * nj];
+        for i in 0..ni {
+            for j in 0..nj {
+                u[i * nj + j] = f(i, j);
+            }
+        }
+        let mut expected = vec![0.; ni * nj];
+        signed_distance_2d(&mut expected, &u, (ni, nj), h);
+        let mut tight = vec![0.; ni * nj];
+        signed_distance_strided(&mut tight, &u, (ni, nj), (nj, nj), h);
+        assert_eq!(tight, expected);
+
+        // the region with an offset of 3 in a buffer with rows of 30 and 40 values
+        let big_u: Vec<f64> = (0..ni * 30 + 3)
+            .map(|s| {
+                if s % 30 >= 3 && s % 30 < nj + 3 {
+                    f(s / 30, s % 30 - 3)
+                } else {
+                    -1.
+                }
+            })
+            .collect();
+        let mut big_d = vec![7.; ni * 40 + 3];
+        signed_distance_strided(&mut big_d[3..], &big_u[3..], (ni, nj), (40, 30), h);
+        for s in 0..big_d.len() {
+            let (i, j) = (s / 40, s % 40);
+            if s < 3 || j < 3 || j >= nj + 3 || i >= ni {
+                assert_eq!(big_d[s], 7., "outside of the region at {}", s);
+            } else {
+                assert_eq!(big_d[s], tight[i * nj + j - 3]);
+            }
+        }
+
+        // a single column of the grid, solved in 1D
+        let column: Vec<f64> = (0..ni).map(|i| u[i * nj + 5]).collect();
+        let mut expected = vec![0.; ni];
+        signed_distance_2d(&mut expected, &column, (ni, 1), h);
+        let mut d = vec![0.; ni * nj];
+        signed_distance_strided(&mut d[5..], &u[5..], (ni, 1), (nj, nj), h);
+        for i in 0..ni {
+            assert_eq!(d[i * nj + 5], expected[i]);
+        }
+    }
+
+    #[test]
+    fn column_major_matches_row_major() {
+        let (ni, nj) = (13, 21);
+        let h = 0.05;
+        let f = |i: usize, j: usize| (i as f64 * h - 0.3).hypot(j as f64 * h - 0.55) - 0.25;
+        let mut u = vec![0.; ni * nj];
+        let mut uc = vec![0.; ni * nj];
+        for i in 0..ni {
+            for j in 0..nj {
+                u[i * nj + j] = f(i, j);
+                uc[j * ni + i] = f(i, j);
+            }
+        }
+        let mut d = vec![0.; ni * nj];
+        signed_distance_2d(&mut d, &u, (ni, nj), h);
+        let mut dc = vec![0.; ni * nj];
+        assert!(signed_distance_colmajor(&mut dc, &uc, (ni, nj), h));
+        for i in 0..ni {
+            for j in 0..nj {
+                let (a, b) = (d[i * nj + j], dc[j * ni + i]);
+                assert!((a - b).abs() < 1e-12, "({}, {}): {} != {}", i, j, a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn solvers_on_same_initialization() {
+        let (ni, nj) = (19, 24);
+        let h = 0.05;
+        let mut u = vec![0.; ni * nj];
+        for i in 0..ni {
+            for j in 0..nj {
+                u[i * nj + j] = (i as f64 * h - 0.45).hypot(j as f64 * h - 0.6) - 0.3;
+            }
+        }
+        let mut expected = vec![0.; ni * nj];
+        signed_distance_2d(&mut expected, &u, (ni, nj), h);
+
+        let mut d = vec![0.; ni * nj];
+        assert!(signed_distance_2d_with(&mut d, &u, (ni, nj), h, &eikonal::FastSweeping));
+        assert_eq!(d, expected);
+    }
+
+    #[test]
+    fn unsigned_is_abs_of_signed() {
+        let n = 25;
+        let h = 1. / (n - 1) as f64;
+        let mut u = vec![0.; n * n];
+        for i in 0..n {
+            for j in 0..n {
+                let (x, y) = (i as f64 * h - 0.4, j as f64 * h - 0.55);
+                u[i * n + j] = x.hypot(y) - 0.25;
+            }
+        }
+        let mut d = vec![0.; n * n];
+        signed_distance_2d(&mut d, &u, (n, n), h);
+        let mut ud = vec![0.; n * n];
+        unsigned_distance_2d(&mut ud, &u, (n, n), h);
+        for s in 0..n * n {
+            assert_eq!(ud[s], d[s].abs());
+        }
+
+        unsigned_distance_2d(&mut ud, &vec![-1.; n * n], (n, n), h);
+        assert!(ud.iter().all(|&d| d == std::f64::MAX));
+
+        // a single row or column
+        let u = [1., 0.5, -0.5, -1., -0.5, 0.5];
+        for &dim in &[(1, 6), (6, 1)] {
+            let mut ud = [0.; 6];
+            unsigned_distance_2d(&mut ud, &u, dim, 0.5);
+            assert_eq!(ud, [0.75, 0.25, 0.25, 0.75, 0.25, 0.25]);
+        }
+    }
+
+    #[test]
+    fn reports_whether_level_set_crosses() {
+        let n = 6;
+        let mut d = vec![0.; n * n];
+        let u: Vec<f64> = (0..n * n).map(|s| (s / n) as f64 - 2.5).collect();
+        assert!(signed_distance_2d(&mut d, &u, (n, n), 0.5));
+        let mut e = vec![0.; n * n];
+        let seeded = level_set::init_dist_2d(&mut e, &u, (n, n), |p| EuclideanNorm.dual_norm(p));
+        assert_eq!(seeded, 2 * n);
+        for &c in &[1., -1.] {
+            assert!(!signed_distance_2d(&mut d, &vec![c; n * n], (n, n), 0.5));
+            assert!(d.iter().all(|&d| d == c * std::f64::MAX));
+            assert!(!signed_distance_2d(&mut d[..n], &vec![c; n], (1, n), 0.5));
+            let mut d3 = vec![0.; 27];
+            assert!(!signed_distance_3d(&mut d3, &vec![c; 27], (3, 3, 3), 0.5));
+        }
+        // touching the level set counts
+        let mut u = vec![1.; n * n];
+        u[8] = 0.;
+        assert!(signed_distance_2d(&mut d, &u, (n, n), 0.5));
+        assert!(signed_distance_1d(&mut d[..n], &u[6..12], 0.5));
+        assert!(!signed_distance_2d(&mut [], &[], (0, 3), 0.5));
+    }
+
+    #[test]
+    fn distance_to_iso_level() {
+        let n = 25;
+        let h = 1. / (n - 1) as f64;
+        let mut r = vec![0.; n * n];
+        for i in 0..n {
+            for j in 0..n {
+                r[i * n + j] = (i as f64 * h - 0.4).hypot(j as f64 * h - 0.55);
+            }
+        }
+        for &level in &[0.15, 0.3] {
+            let mut d = vec![0.; n * n];
+            signed_distance_level(&mut d, &r, (n, n), h, level);
+            let shifted: Vec<f64> = r.iter().map(|r| r - level).collect();
+            let mut e = vec![0.; n * n];
+            signed_distance_2d(&mut e, &shifted, (n, n), h);
+            assert_eq!(d, e, "level {}", level);
+            assert!(d[10 * n + 13] < 0., "the center must be inside");
+        }
+
+        // a single row or column
+        let u = [1., 0.5, -0.5, -1., -0.5, 0.5];
+        let shifted: Vec<f64> = u.iter().map(|u| u - 0.25).collect();
+        let mut e = [0.; 6];
+        signed_distance_2d(&mut e, &shifted, (1, 6), 0.5);
+        for &dim in &[(1, 6), (6, 1)] {
+            let mut d = [0.; 6];
+            signed_distance_level(&mut d, &u, dim, 0.5, 0.25);
+            assert_eq!(d, e, "{:?}", dim);
+        }
+    }
+
+    #[test]
+    fn several_levels_at_once() {
+        let (ni, nj) = (17, 22);
+        let h = 0.05;
+        let mut u = vec![0.; ni * nj];
+        for i in 0..ni {
+            for j in 0..nj {
+                let (x, y) = (i as f64 * h - 0.4, j as f64 * h - 0.5);
+                u[i * nj + j] = x * x + 2. * y * y + 0.3 * x * y;
+            }
+        }
+        let levels = [0.01, 0.05, 0.2, -1., 10.];
+        let ds = signed_distance_levels(&u, (ni, nj), h, &levels);
+        assert_eq!(ds.len(), levels.len());
+        for (d, &c) in ds.iter().zip(&levels) {
+            let mut e = vec![0.; ni * nj];
+            signed_distance_level(&mut e, &u, (ni, nj), h, c);
+            assert_eq!(d, &e, "level {}", c);
+        }
+        assert!(ds[3].iter().all(|&d| d == std::f64::MAX));
+        assert!(ds[4].iter().all(|&d| d == -std::f64::MAX));
+    }
+
+    #[test]
+    fn two_points_voronoi_boundary() {
+        let (ni, nj) = (19, 23);
+        let h = 0.1;
+        let points = [(7.3, 6.4), (7.3, 15.6)];
+        let mut d = vec![0.; ni * nj];
+        distance_from_points(&mut d, &points, (ni, nj), h);
+        for i in 0..ni {
+            for j in 0..nj {
+                let e = points
+                    .iter()
+                    .map(|&(x, y)| (x - i as f64).hypot(y - j as f64) * h)
+                    .fold(std::f64::MAX, min);
+                let s = i * nj + j;
+                assert!((d[s] - e).abs() < h, "{:?}: {} != {}", (i, j), d[s], e);
+                // the points are symmetric about the column 11, the boundary of their Voronoi cells
+                let t = i * nj + 22 - j;
+                assert!((d[s] - d[t]).abs() < 1e-9, "{} != {}", d[s], d[t]);
+            }
+        }
+
+        distance_from_points(&mut d, &[], (ni, nj), h);
+        assert!(d.iter().all(|&d| d == std::f64::MAX));
+    }
+
+    #[test]
+    fn profile_1d() {
+        let n = 41;
+        let h = 0.025;
+        let f = |x: f64| (x - 0.3) * (x - 0.65);
+        let u: Vec<f64> = (0..n).map(|k| f(k as f64 * h)).collect();
+        let mut d = vec![0.; n];
+        signed_distance_1d(&mut d, &u, h);
+        for k in 0..n {
+            let x = k as f64 * h;
+            let e = if x < 0.475 { 0.3 - x } else { x - 0.65 };
+            // the zeros of the piecewise linear interpolation are off by O(h²)
+            assert!((d[k] - e).abs() < h * h, "{}: {} != {}", k, d[k], e);
+        }
+
+        signed_distance_1d(&mut d[..5], &[-1.; 5], h);
+        assert!(d[..5].iter().all(|&d| d == -std::f64::MAX));
+    }
+
+    #[test]
+    fn degenerate_grids() {
+        let h = 0.5;
+        let u = [1., 0.5, -0.5, -1., -0.5, 0.5];
+        let mut expected = [0.; 6];
+        signed_distance_1d(&mut expected, &u, h);
+        assert_eq!(expected, [0.75, 0.25, -0.25, -0.75, -0.25, 0.25]);
+        for &dim in &[(1, 6), (6, 1)] {
+            let mut d = [0.; 6];
+            signed_distance_2d(&mut d, &u, dim, h);
+            assert_eq!(d, expected);
+        }
+
+        let mut d: [f64; 0] = [];
+        signed_distance_2d(&mut d, &[], (0, 0), h);
+        signed_distance_2d(&mut d, &[], (0, 5), h);
+
+        // the fallible variant accepts the same grids
+        let mut d = [0.; 6];
+        assert_eq!(try_signed_distance_2d(&mut d, &u, (1, 6), h), Ok(()));
+        assert_eq!(d, expected);
+        assert_eq!(try_signed_distance_2d(&mut [], &[], (0, 0), h), Ok(()));
+        assert_eq!(
+            try_signed_distance_2d(&mut d, &u, (2, 3), h),
+            Err(SweepError::GridTooSmall {
+                axis: 0,
+                len: 2,
+                min: 3
+            })
+        );
+    }
+
+    #[test]
+    fn mask_boundary_between_nodes() {
+        let (ni, nj) = (20, 15);
+        let h = 0.1;
+        let mask: Vec<bool> = (0..ni * nj).map(|s| s / nj < 10).collect();
+        let mut d = vec![0.; ni * nj];
+        distance_from_mask(&mut d, &mask, (ni, nj), h);
+        for s in 0..ni * nj {
+            let e = ((s / nj) as f64 - 9.5) * h;
+            assert!((d[s] - e).abs() < 1e-12, "{} != {}", d[s], e);
+        }
+
+        distance_from_mask(&mut d, &vec![true; ni * nj], (ni, nj), h);
+        assert!(d.iter().all(|&d| d == -std::f64::MAX));
+    }
+
+    #[test]
+    fn distance_within_labeled_regions() {
+        let (ni, nj) = (12, 16);
+        let h = 0.1;
+        // three stripes, the boundaries halfway between the columns 4 and 5, and 9 and 10
+        let labels: Vec<u32> = (0..ni * nj)
+            .map(|s| [7, 3, 10][std::cmp::min(s % nj, 10) / 5])
+            .collect();
+        let mut d = vec![0.; ni * nj];
+        signed_distance_labels(&mut d, &labels, (ni, nj), h);
+        for s in 0..ni * nj {
+            let j = (s % nj) as f64;
+            let e = h * min((j - 4.5).abs(), (j - 9.5).abs());
+            assert!((d[s] - e).abs() < 1e-12, "{} != {}", d[s], e);
+        }
+
+        // two labels are the mask and its complement
+        let mut u = vec![0.; ni * nj];
+        for i in 0..ni {
+            for j in 0..nj {
+                u[i * nj + j] = (i as f64 - 5.2).hypot(j as f64 - 8.1) - 3.7;
+            }
+        }
+        let mask: Vec<bool> = u.iter().map(|&u| u < 0.).collect();
+        let labels: Vec<u32> = mask.iter().map(|&m| m as u32).collect();
+        signed_distance_labels(&mut d, &labels, (ni, nj), h);
+        let mut e = vec![0.; ni * nj];
+        distance_from_mask(&mut e, &mask, (ni, nj), h);
+        for s in 0..ni * nj {
+            assert!((d[s] - e[s].abs()).abs() < 1e-12, "{} != {}", d[s], e[s]);
+        }
+
+        signed_distance_labels(&mut d, &vec![2; ni * nj], (ni, nj), h);
+        assert!(d.iter().all(|&d| d == std::f64::MAX));
+    }
+
+    #[test]
+    fn single_precision_circle() {
+        let n = 41;
+        let h = 1. / (n - 1) as f64;
+        let mut u = vec![0.; n * n];
+        for i in 0..n {
+            for j in 0..n {
+                let (x, y) = (i as f64 * h - 0.5, j as f64 * h - 0.5);
+                u[i * n + j] = x.hypot(y) - 0.3;
+            }
+        }
+        let mut d = vec![0.; n * n];
+        signed_distance_2d(&mut d, &u, (n, n), h);
+
+        let u32: Vec<f32> = u.iter().map(|&u| u as f32).collect();
+        let mut d32 = vec![0f32; n * n];
+        signed_distance_2d_float(&mut d32, &u32, (n, n), h as f32);
+        for s in 0..n * n {
+            assert!(
+                (d32[s] as f64 - d[s]).abs() < 1e-4,
+                "{} != {} at {}",
+                d32[s],
+                d[s],
+                s
+            );
+        }
+
+        let mut all = vec![0f32; 9];
+        signed_distance_2d_float(&mut all, &[1f32; 9], (3, 3), 0.5);
+        assert!(all.iter().all(|&d| d == std::f32::MAX));
+    }
 }
@@ -0,0 +1,349 @@
+//! Measures of the region enclosed by the zero level set and of its boundary.
+//!
+//! The level set function is assumed to be linear on each of the 6 tetrahedra of every cube of
+//! the grid, as in [`init_dist_3d`](../level_set/fn.init_dist_3d.html), and in 2D on each of the
+//! 2 triangles of every square, as in [`init_dist_2d`](../level_set/fn.init_dist_2d.html). The
+//! region is where the function is negative.
+
+use contour::contour_length;
+
+/// Orders in which the coordinates change along the edges of the 6 tetrahedra of a cube.
+const TETRAHEDRA: [[usize; 3]; 6] = [
+    [0, 1, 2],
+    [0, 2, 1],
+    [1, 0, 2],
+    [1, 2, 0],
+    [2, 0, 1],
+    [2, 1, 0],
+];
+
+/// Calls `f` with the vertex coordinates (in the units of the grid step) and the values of `u` for
+/// every tetrahedron of the grid.
+fn for_each_tetrahedron<F>(u: &[f64], dim: (usize, usize, usize), mut f: F)
+where
+    F: FnMut([[f64; 3]; 4], [f64; 4]),
+{
+    let (ni, nj, nk) = dim;
+    assert_eq!(ni * nj * nk, u.len());
+    let index = |p: [usize; 3]| (p[0] * nj + p[1]) * nk + p[2];
+
+    for i in 1..ni {
+        for j in 1..nj {
+            for k in 1..nk {
+                for order in &TETRAHEDRA {
+                    let mut p = [i - 1, j - 1, k - 1];
+                    let mut x = [[0.; 3]; 4];
+                    let mut v = [0.; 4];
+                    for step in 0..4 {
+                        if step > 0 {
+                            p[order[step - 1]] += 1;
+                        }
+                        x[step] = [p[0] as f64, p[1] as f64, p[2] as f64];
+                        v[step] = u[index(p)];
+                    }
+                    f(x, v);
+                }
+            }
+        }
+    }
+}
+
+fn sub(x: [f64; 3], y: [f64; 3]) -> [f64; 3] {
+    [x[0] - y[0], x[1] - y[1], x[2] - y[2]]
+}
+
+fn cross(x: [f64; 3], y: [f64; 3]) -> [f64; 3] {
+    [
+        x[1] * y[2] - x[2] * y[1],
+        x[2] * y[0] - x[0] * y[2],
+        x[0] * y[1] - x[1] * y[0],
+    ]
+}
+
+fn dot(x: [f64; 3], y: [f64; 3]) -> f64 {
+    x[0] * y[0] + x[1] * y[1] + x[2] * y[2]
+}
+
+fn tetrahedron_volume(a: [f64; 3], b: [f64; 3], c: [f64; 3], d: [f64; 3]) -> f64 {
+    dot(sub(b, a), cross(sub(c, a), sub(d, a))).abs() / 6.
+}
+
+fn triangle_area(a: [f64; 3], b: [f64; 3], c: [f64; 3]) -> f64 {
+    let n = cross(sub(b, a), sub(c, a));
+    0.5 * dot(n, n).sqrt()
+}
+
+/// Point where the zero level set crosses the edge from `x` to `y`.
+fn crossing(x: [f64; 3], ux: f64, y: [f64; 3], uy: f64) -> [f64; 3] {
+    let t = ux / (ux - uy);
+    [
+        x[0] + t * (y[0] - x[0]),
+        x[1] + t * (y[1] - x[1]),
+        x[2] + t * (y[2] - x[2]),
+    ]
+}
+
+/// Splits the vertices of a tetrahedron into the ones with negative and nonnegative values.
+fn split(v: [f64; 4]) -> (Vec<usize>, Vec<usize>) {
+    (0..4).partition(|&i| v[i] < 0.)
+}
+
+/// Fraction of the area of a triangle where the linear function with the vertex values `v` is
+/// negative.
+fn triangle_fraction(v: [f64; 3]) -> f64 {
+    let neg: Vec<_> = (0..3).filter(|&i| v[i] < 0.).collect();
+    match neg.len() {
+        0 => 0.,
+        3 => 1.,
+        1 => {
+            let a = neg[0];
+            // the product of the fractions of the two edges from `a` that are negative
+            (0..3)
+                .filter(|&b| b != a)
+                .map(|b| v[a] / (v[a] - v[b]))
+                .product()
+        }
+        _ => {
+            let p = (0..3).find(|&i| v[i] >= 0.).unwrap();
+            1. - (0..3)
+                .filter(|&b| b != p)
+                .map(|b| v[p] / (v[p] - v[b]))
+                .product::<f64>()
+        }
+    }
+}
+
+/// Computes the area of the region `{u < 0}`, where `u` is given on a regular 2D grid with
+/// dimensions `dim` and spacing `h`, for instance the signed distance.
+///
+/// Instead of counting the negative nodes, the crossings of the zero level set are located on the
+/// edges by linear interpolation. The result is exact for piecewise linear `u`, and for smooth
+/// level sets the error is of order `h²`.
+pub fn enclosed_area(u: &[f64], dim: (usize, usize), h: f64) -> f64 {
+    let (ni, nj) = dim;
+    assert_eq!(ni * nj, u.len());
+
+    let mut area = 0.;
+    for i in 1..ni {
+        for j in 1..nj {
+            let s = i * nj + j;
+            let v = [u[s - nj - 1], u[s - nj], u[s - 1], u[s]];
+            area += 0.5 * triangle_fraction([v[0], v[1], v[3]]);
+            area += 0.5 * triangle_fraction([v[0], v[2], v[3]]);
+        }
+    }
+    area * h * h
+}
+
+/// Computes the length of the zero level set of `u` given on a regular 2D grid with dimensions
+/// `dim` and spacing `h`, for instance the signed distance.
+///
+/// The level set is approximated by one segment in every triangle that it crosses (marching
+/// triangles), the same as [`contour_length`](../contour/fn.contour_length.html). Unlike counting
+/// the cells along the boundary, this does not overestimate the length of diagonal boundaries,
+/// and for smooth level sets the error is of order `h²`.
+///
+/// A sharp corner is biased low: the segment through the triangle that contains the corner cuts
+/// it off, and the length of a polygon is underestimated by up to about `(2 - √2) h` for every
+/// right angle, an error of order `h` whatever the smoothness of the rest of the boundary.
+pub fn interface_length(u: &[f64], dim: (usize, usize), h: f64) -> f64 {
+    contour_length(u, dim, h)
+}
+
+/// Computes the volume of the region `{u < 0}`, where `u` is given on a regular 3D grid with
+/// dimensions `dim` and spacing `h`.
+///
+/// The result is exact for piecewise linear `u`, and for smooth level sets the error is of order
+/// `h²`.
+pub fn enclosed_volume(u: &[f64], dim: (usize, usize, usize), h: f64) -> f64 {
+    let mut vol = 0.;
+    for_each_tetrahedron(u, dim, |x, v| {
+        let (neg, pos) = split(v);
+        vol += match neg.len() {
+            0 => 0.,
+            4 => 1. / 6.,
+            1 => {
+                let n = neg[0];
+                let c: Vec<_> = pos
+                    .iter()
+                    .map(|&p| crossing(x[n], v[n], x[p], v[p]))
+                    .collect();
+                tetrahedron_volume(x[n], c[0], c[1], c[2])
+            }
+            3 => {
+                let p = pos[0];
+                let c: Vec<_> = neg
+                    .iter()
+                    .map(|&n| crossing(x[p], v[p], x[n], v[n]))
+                    .collect();
+                1. / 6. - tetrahedron_volume(x[p], c[0], c[1], c[2])
+            }
+            _ => {
+                // a prism with the triangles (a, ac, ad) and (b, bc, bd)
+                let (a, b, c, d) = (neg[0], neg[1], pos[0], pos[1]);
+                let ac = crossing(x[a], v[a], x[c], v[c]);
+                let ad = crossing(x[a], v[a], x[d], v[d]);
+                let bc = crossing(x[b], v[b], x[c], v[c]);
+                let bd = crossing(x[b], v[b], x[d], v[d]);
+                tetrahedron_volume(x[a], ac, ad, x[b])
+                    + tetrahedron_volume(ac, ad, x[b], bc)
+                    + tetrahedron_volume(ad, x[b], bc, bd)
+            }
+        };
+    });
+    vol * h * h * h
+}
+
+/// Computes the area of the zero level set of `u` given on a regular 3D grid with dimensions `dim`
+/// and spacing `h`.
+///
+/// The level set is approximated by one triangle or quadrilateral in every tetrahedron that it
+/// crosses (marching tetrahedra). Like for [`interface_length`](fn.interface_length.html), sharp
+/// edges and corners are cut off and the area is biased low there, by an amount of order `h`
+/// times the length of the edges.
+pub fn surface_area(u: &[f64], dim: (usize, usize, usize), h: f64) -> f64 {
+    let mut area = 0.;
+    for_each_tetrahedron(u, dim, |x, v| {
+        let (neg, pos) = split(v);
+        area += match neg.len() {
+            0 | 4 => 0.,
+            1 | 3 => {
+                let (a, others) = if neg.len() == 1 {
+                    (neg[0], pos)
+                } else {
+                    (pos[0], neg)
+                };
+                let c: Vec<_> = others
+                    .iter()
+                    .map(|&b| crossing(x[a], v[a], x[b], v[b]))
+                    .collect();
+                triangle_area(c[0], c[1], c[2])
+            }
+            _ => {
+                let (a, b, c, d) = (neg[0], neg[1], pos[0], pos[1]);
+                let ac = crossing(x[a], v[a], x[c], v[c]);
+                let ad = crossing(x[a], v[a], x[d], v[d]);
+                let bc = crossing(x[b], v[b], x[c], v[c]);
+                let bd = crossing(x[b], v[b], x[d], v[d]);
+                triangle_area(ac, ad, bd) + triangle_area(ac, bd, bc)
+            }
+        };
+    });
+    area * h * h
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    fn sphere(n: usize, r: f64) -> (Vec<f64>, f64) {
+        let h = 1. / (n - 1) as f64;
+        let mut u = vec![0.; n * n * n];
+        for i in 0..n {
+            for j in 0..n {
+                for k in 0..n {
+                    let x = i as f64 * h - 0.5;
+                    let y = j as f64 * h - 0.48;
+                    let z = k as f64 * h - 0.53;
+                    u[(i * n + j) * n + k] = (x * x + y * y + z * z).sqrt() - r;
+                }
+            }
+        }
+        (u, h)
+    }
+
+    #[test]
+    fn sphere_volume_and_area() {
+        let n = 33;
+        let r = 0.3;
+        let (u, h) = sphere(n, r);
+        let vol = enclosed_volume(&u, (n, n, n), h);
+        let exact = 4. / 3. * PI * r.powi(3);
+        assert!((vol - exact).abs() < 0.01 * exact, "{} != {}", vol, exact);
+        let area = surface_area(&u, (n, n, n), h);
+        let exact = 4. * PI * r * r;
+        assert!((area - exact).abs() < 0.02 * exact, "{} != {}", area, exact);
+    }
+
+    #[test]
+    fn circle_area_converges() {
+        let r = 0.3;
+        let exact = PI * r * r;
+        let error = |n: usize| {
+            let h = 1. / (n - 1) as f64;
+            let mut u = vec![0.; n * n];
+            for i in 0..n {
+                for j in 0..n {
+                    let (x, y) = (i as f64 * h - 0.5, j as f64 * h - 0.48);
+                    u[i * n + j] = x.hypot(y) - r;
+                }
+            }
+            let mut d = vec![0.; n * n];
+            ::signed_distance_2d(&mut d, &u, (n, n), h);
+            (enclosed_area(&d, (n, n), h) - exact).abs()
+        };
+        let (coarse, fine) = (error(33), error(65));
+        assert!(coarse < 0.01 * exact, "{}", coarse);
+        assert!(fine < 0.5 * coarse, "{} {}", fine, coarse);
+
+        // the half plane x + y < 1 of the unit square
+        let n = 5;
+        let h = 1. / (n - 1) as f64;
+        let u: Vec<f64> = (0..n * n)
+            .map(|s| (s / n + s % n) as f64 * h - 1.)
+            .collect();
+        assert!((enclosed_area(&u, (n, n), h) - 0.5).abs() < 1e-12);
+        assert_eq!(enclosed_area(&vec![-1.; n * n], (n, n), h), 1.);
+    }
+
+    #[test]
+    fn square_length_is_biased_low() {
+        let n = 41;
+        let h = 1. / (n - 1) as f64;
+        let a = 0.23;
+        let mut u = vec![0.; n * n];
+        for i in 0..n {
+            for j in 0..n {
+                let (x, y) = (i as f64 * h - 0.47, j as f64 * h - 0.52);
+                u[i * n + j] = x.abs().max(y.abs()) - a;
+            }
+        }
+        let mut d = vec![0.; n * n];
+        ::signed_distance_2d(&mut d, &u, (n, n), h);
+        let len = interface_length(&d, (n, n), h);
+        let bias = 4. * (2. - 2f64.sqrt()) * h;
+        assert!(len < 8. * a && len > 8. * a - bias, "{}", len);
+
+        for i in 0..n {
+            for j in 0..n {
+                let (x, y) = (i as f64 * h - 0.45, j as f64 * h - 0.5);
+                u[i * n + j] = x.hypot(y) - 0.3;
+            }
+        }
+        ::signed_distance_2d(&mut d, &u, (n, n), h);
+        let len = interface_length(&d, (n, n), h);
+        assert!((len - 0.6 * PI).abs() < 1e-3, "{}", len);
+    }
+
+    #[test]
+    fn linear_volume_is_exact() {
+        // the plane x + y + z = 1 cuts off a corner of the unit cube
+        let n = 5;
+        let h = 1. / (n - 1) as f64;
+        let mut u = vec![0.; n * n * n];
+        for i in 0..n {
+            for j in 0..n {
+                for k in 0..n {
+                    u[(i * n + j) * n + k] = (i + j + k) as f64 * h - 1.;
+                }
+            }
+        }
+        assert!((enclosed_volume(&u, (n, n, n), h) - 1. / 6.).abs() < 1e-12);
+        let area = 3f64.sqrt() / 2.;
+        assert!((surface_area(&u, (n, n, n), h) - area).abs() < 1e-12);
+        let all = vec![-1.; n * n * n];
+        assert!((enclosed_volume(&all, (n, n, n), h) - 1.).abs() < 1e-12);
+        assert_eq!(surface_area(&all, (n, n, n), h), 0.);
+    }
+}
@@ -6,6 +6,7 @@
 //! The correctness of the implementation can be tested using `test_inv_dual_norm_2d` and
 //! `test_inv_dual_norm_3d` functions.
 use super::{max, min};
+use num_traits::Float;
 
 /// Trait for setting up anisotropic distance function computation.
 ///
@@ -48,6 +49,27 @@ impl DualNorm<[f64; 2], f64> for EuclideanNorm {
     }
 }
 
+/// The Euclidean dual norm in 2D for any floating point type, as used by
+/// [`signed_distance_2d_float`](../fn.signed_distance_2d_float.html).
+pub(crate) fn euclidean_dual_norm_2d<T: Float>(p: [T; 2]) -> T {
+    (p[0] * p[0] + p[1] * p[1]).sqrt()
+}
+
+/// The solution of the inverse problem for the Euclidean norm in 2D like
+/// `EuclideanNorm::inv_dual_norm`, for any floating point type.
+pub(crate) fn euclidean_inv_dual_norm_2d<T: Float>(d: T, v: [T; 2]) -> T {
+    let (a, b) = (v[0], v[1]);
+    let (one, two) = (T::one(), T::one() + T::one());
+
+    let x = if (a - b).abs() >= one {
+        a.min(b) + one
+    } else {
+        (a + b + (two - (a - b) * (a - b)).sqrt()) / two
+    };
+
+    d.min(x)
+}
+
 /// Dual norm for the max (l∞) norm is the l¹ norm.
 impl DualNorm<[f64; 3], f64> for EuclideanNorm {
     #[inline(always)]
@@ -163,6 +185,94 @@ impl DualNorm<[f64; 3], f64> for MaxNorm {
     }
 }
 
+/// Euclidean norm in the world coordinates of a grid obtained by an affine transformation of the
+/// integer lattice, such as a sheared or rotated grid.
+///
+/// The node with indices `(i, j)` is at the position `x = o + A (i, j)ᵀ` for some origin `o`, that
+/// is, the columns of `A` are the world space vectors between neighboring nodes. The norm of an
+/// index space vector `v` is then `‖A v‖`. The origin does not influence the distance.
+#[derive(Clone, Copy, Debug)]
+pub struct AffineNorm {
+    /// The metric tensor `AᵀA`.
+    g: [[f64; 2]; 2],
+    /// The inverse of the metric tensor.
+    m: [[f64; 2]; 2],
+}
+
+impl AffineNorm {
+    /// Creates the norm for the grid with the transform `A`, given as `[[a11, a12], [a21, a22]]`.
+    ///
+    /// Panics if the transform is singular.
+    pub fn new(a: [[f64; 2]; 2]) -> AffineNorm {
+        let g11 = a[0][0] * a[0][0] + a[1][0] * a[1][0];
+        let g12 = a[0][0] * a[0][1] + a[1][0] * a[1][1];
+        let g22 = a[0][1] * a[0][1] + a[1][1] * a[1][1];
+        let det = g11 * g22 - g12 * g12;
+        assert!(det > 0., "The transform must be invertible, got {:?}", a);
+        AffineNorm {
+            g: [[g11, g12], [g12, g22]],
+            m: [[g22 / det, -g12 / det], [-g12 / det, g11 / det]],
+        }
+    }
+
+    /// Creates the norm `‖v‖ = √(vᵀ g v)` for the metric tensor `g = AᵀA`, given as
+    /// `[[g11, g12], [g12, g22]]`.
+    ///
+    /// Panics if the metric is not positive definite.
+    pub fn from_metric(g: [[f64; 2]; 2]) -> AffineNorm {
+        let (g11, g12, g22) = (g[0][0], g[0][1], g[1][1]);
+        let det = g11 * g22 - g12 * g12;
+        assert!(
+            g11 > 0. && det > 0.,
+            "The metric must be positive definite, got {:?}",
+            g
+        );
+        AffineNorm {
+            g: [[g11, g12], [g12, g22]],
+            m: [[g22 / det, -g12 / det], [-g12 / det, g11 / det]],
+        }
+    }
+}
+
+/// The dual norm is `‖A⁻ᵀ p‖`.
+///
+/// On a sheared grid, the triangle formed by a node and its two upwind neighbors might be obtuse
+/// and the "inverse problem" does not give a consistent discretization. Instead, `inv_dual_norm`
+/// uses the semi-Lagrangian (Hopf-Lax) update: the minimum of `λ v₁ + (1 - λ) v₂ + ‖w‖` over the
+/// points `w = -λ s₁ e₁ - (1 - λ) s₂ e₂` on the segment between the neighbors. For orthogonal grids
+/// the two coincide.
+impl DualNorm<[f64; 2], f64> for AffineNorm {
+    #[inline(always)]
+    fn dual_norm(&self, p: [f64; 2]) -> f64 {
+        let m = &self.m;
+        (m[0][0] * p[0] * p[0] + 2. * m[0][1] * p[0] * p[1] + m[1][1] * p[1] * p[1]).sqrt()
+    }
+
+    #[inline(always)]
+    fn inv_dual_norm(&self, d: f64, v: [f64; 2], s: [f64; 2]) -> f64 {
+        let g = &self.g;
+        // ‖w‖² = a λ² + 2 b λ + c
+        let a = g[0][0] - 2. * s[0] * s[1] * g[0][1] + g[1][1];
+        let b = s[0] * s[1] * g[0][1] - g[1][1];
+        let c = g[1][1];
+
+        // the endpoints of the segment
+        let mut t = min(v[0] + g[0][0].sqrt(), v[1] + g[1][1].sqrt());
+
+        // the critical point in the interior
+        let delta = v[1] - v[0];
+        let k = a - delta * delta;
+        if k > 0. {
+            let l = (-b + delta * ((a * c - b * b).max(0.) / k).sqrt()) / a;
+            if l > 0. && l < 1. {
+                t = min(t, v[1] - l * delta + (a * l * l + 2. * b * l + c).sqrt());
+            }
+        }
+
+        min(d, t)
+    }
+}
+
 /// Convenience function to test the consistency of the `inv_dual_norm` implementation with
 /// `dual_norm` by generating vectors on a `(n + 1)^3` grid with values in the interval [-m, m].
 pub fn test_inv_dual_norm_2d<N>(norm: N, m: f64, n: u32)
@@ -253,4 +363,11 @@ mod tests {
     fn dual_norm_l1_norm() {
         test_inv_dual_norm_2d(L1Norm, 2., 5);
     }
+
+    #[test]
+    fn dual_norm_affine_norm() {
+        // orthogonal grids, where the update solves the inverse problem
+        test_inv_dual_norm_2d(AffineNorm::new([[1., 0.], [0., 1.]]), 2., 5);
+        test_inv_dual_norm_2d(AffineNorm::new([[0.6, -0.8], [0.8, 0.6]]), 2., 5);
+    }
 }
@@ -0,0 +1,213 @@
+//! Export and import of 2D fields in the NumPy `.npy` format.
+//!
+//! The fields of this crate are stored in the row-major (C) order, the node `(i, j)` at the index
+//! `i * dim.1 + j`, which is the default of NumPy: an array `u` of shape `(ni, nj)` saved by
+//! `np.save` loads as the level set function with `dim = (ni, nj)`, and the saved distance loads in
+//! NumPy with `u[i, j]` the value at the node `(i, j)`. Arrays in the Fortran order are transposed
+//! into the row-major order on loading, so they keep their shape.
+//!
+//! Requires the `npy` feature.
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+const MAGIC: &[u8] = b"\x93NUMPY";
+
+/// Saves the 2D field `d` with dimensions `dim` as a little-endian `float64` array of shape
+/// `dim` to the file `path`.
+pub fn save_npy<P: AsRef<Path>>(path: P, d: &[f64], dim: (usize, usize)) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    write_npy(&mut out, d, dim)?;
+    out.flush()
+}
+
+/// Loads a 2D array from the `.npy` file `path` as the level set function for
+/// [`signed_distance_2d`](../fn.signed_distance_2d.html), see [`read_npy`](fn.read_npy.html).
+///
+/// Returns the values in the row-major order and the dimensions.
+pub fn load_level_set_npy<P: AsRef<Path>>(path: P) -> io::Result<(Vec<f64>, (usize, usize))> {
+    read_npy(&mut BufReader::new(File::open(path)?))
+}
+
+/// Writes the 2D field `d` with dimensions `dim` in the `.npy` format (version 1.0) to `out`.
+pub fn write_npy<W: Write>(out: &mut W, d: &[f64], dim: (usize, usize)) -> io::Result<()> {
+    assert_eq!(dim.0 * dim.1, d.len());
+    let mut header = format!(
+        "{{'descr': '<f8', 'fortran_order': False, 'shape': ({}, {}), }}",
+        dim.0, dim.1
+    );
+    // the data start at a multiple of 64 bytes, the header ends with a newline
+    let len = MAGIC.len() + 2 + 2 + header.len() + 1;
+    for _ in 0..(64 - len % 64) % 64 {
+        header.push(' ');
+    }
+    header.push('\n');
+
+    out.write_all(MAGIC)?;
+    out.write_all(&[1, 0])?;
+    out.write_all(&[header.len() as u8, (header.len() >> 8) as u8])?;
+    out.write_all(header.as_bytes())?;
+    for &d in d {
+        out.write_all(&d.to_bits().to_le_bytes())?;
+    }
+    Ok(())
+}
+
+/// Reads a 2D array in the `.npy` format from `r`.
+///
+/// The supported types are `float64` and `float32` of either byte order; the values are
+/// converted to `f64`. An array in the Fortran order is transposed, so that the result is always
+/// in the row-major order of this crate with the dimensions equal to the shape of the array.
+///
+/// Returns an error of the kind `InvalidData` if the data are not a 2D array of a supported type.
+pub fn read_npy<R: Read>(r: &mut R) -> io::Result<(Vec<f64>, (usize, usize))> {
+    let mut magic = [0; 8];
+    r.read_exact(&mut magic)?;
+    if &magic[..6] != MAGIC {
+        return Err(invalid("not an npy file"));
+    }
+    let header_len = match magic[6] {
+        1 => {
+            let mut len = [0; 2];
+            r.read_exact(&mut len)?;
+            u16::from_le_bytes(len) as usize
+        }
+        2 | 3 => {
+            let mut len = [0; 4];
+            r.read_exact(&mut len)?;
+            u32::from_le_bytes(len) as usize
+        }
+        _ => return Err(invalid("unsupported npy version")),
+    };
+    let mut header = vec![0; header_len];
+    r.read_exact(&mut header)?;
+    let header = String::from_utf8_lossy(&header);
+
+    let descr = header_value(&header, "descr")
+        .and_then(|v| v.trim_start_matches('\'').split('\'').next())
+        .ok_or_else(|| invalid("missing descr"))?;
+    let fortran_order = header_value(&header, "fortran_order")
+        .map(|v| v.starts_with("True"))
+        .ok_or_else(|| invalid("missing fortran_order"))?;
+    let shape: Vec<usize> = header_value(&header, "shape")
+        .and_then(|v| v.trim_start_matches('(').split(')').next())
+        .ok_or_else(|| invalid("missing shape"))?
+        .split(',')
+        .map(|n| n.trim())
+        .filter(|n| !n.is_empty())
+        .map(|n| n.parse().map_err(|_| invalid("invalid shape")))
+        .collect::<io::Result<_>>()?;
+    if shape.len() != 2 {
+        return Err(invalid("the array must be 2D"));
+    }
+    let (ni, nj) = (shape[0], shape[1]);
+    let n = ni.checked_mul(nj).ok_or_else(|| invalid("invalid shape"))?;
+
+    // grown as the values are read, so that a corrupted shape does not allocate
+    let mut values = Vec::new();
+    match descr {
+        "<f8" | ">f8" => {
+            let mut bytes = [0; 8];
+            for _ in 0..n {
+                r.read_exact(&mut bytes)?;
+                let bits = if descr == "<f8" {
+                    u64::from_le_bytes(bytes)
+                } else {
+                    u64::from_be_bytes(bytes)
+                };
+                values.push(f64::from_bits(bits));
+            }
+        }
+        "<f4" | ">f4" => {
+            let mut bytes = [0; 4];
+            for _ in 0..n {
+                r.read_exact(&mut bytes)?;
+                let bits = if descr == "<f4" {
+                    u32::from_le_bytes(bytes)
+                } else {
+                    u32::from_be_bytes(bytes)
+                };
+                values.push(f32::from_bits(bits) as f64);
+            }
+        }
+        _ => return Err(invalid("unsupported dtype, expected float64 or float32")),
+    }
+
+    if fortran_order {
+        // the value at `(i, j)` is at `j * ni + i`
+        let mut u = vec![0.; ni * nj];
+        for i in 0..ni {
+            for j in 0..nj {
+                u[i * nj + j] = values[j * ni + i];
+            }
+        }
+        values = u;
+    }
+    Ok((values, (ni, nj)))
+}
+
+/// The text of the header dictionary after the key `key`, or `None` if the key is missing.
+fn header_value<'a>(header: &'a str, key: &str) -> Option<&'a str> {
+    let key = format!("'{}':", key);
+    header
+        .find(&key)
+        .map(|start| header[start + key.len()..].trim_start())
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip_and_fortran_order() {
+        let (ni, nj) = (3, 5);
+        let d: Vec<f64> = (0..ni * nj).map(|s| s as f64 * 0.1 - 0.7).collect();
+        let mut bytes = vec![];
+        write_npy(&mut bytes, &d, (ni, nj)).unwrap();
+        assert_eq!(&bytes[..8], b"\x93NUMPY\x01\x00");
+        // the data are aligned to 64 bytes
+        assert_eq!((bytes.len() - 8 * ni * nj) % 64, 0);
+        assert_eq!(read_npy(&mut &bytes[..]).unwrap(), (d.clone(), (ni, nj)));
+
+        // a big-endian float32 array in the Fortran order, as written by NumPy
+        let header = "{'descr': '>f4', 'fortran_order': True, 'shape': (2, 3), }";
+        let mut bytes = b"\x93NUMPY\x01\x00".to_vec();
+        bytes.extend_from_slice(&[header.len() as u8 + 1, 0]);
+        bytes.extend_from_slice(header.as_bytes());
+        bytes.push(b'\n');
+        // the columns of [[0, 1, 2], [3, 4, 5]]
+        for &v in &[0f32, 3., 1., 4., 2., 5.] {
+            bytes.extend_from_slice(&v.to_bits().to_be_bytes());
+        }
+        let (u, dim) = read_npy(&mut &bytes[..]).unwrap();
+        assert_eq!(dim, (2, 3));
+        assert_eq!(u, vec![0., 1., 2., 3., 4., 5.]);
+
+        let header = "{'descr': '<i8', 'fortran_order': False, 'shape': (2, 3), }\n";
+        let mut bytes = b"\x93NUMPY\x01\x00".to_vec();
+        bytes.extend_from_slice(&[header.len() as u8, 0]);
+        bytes.extend_from_slice(header.as_bytes());
+        let err = read_npy(&mut &bytes[..]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        // shapes that overflow or promise more data than there are
+        for &(shape, kind) in &[
+            ("(4294967296, 4294967296)", io::ErrorKind::InvalidData),
+            ("(1099511627776, 1)", io::ErrorKind::UnexpectedEof),
+        ] {
+            let header = format!(
+                "{{'descr': '<f8', 'fortran_order': False, 'shape': {}, }}\n",
+                shape
+            );
+            let mut bytes = b"\x93NUMPY\x01\x00".to_vec();
+            bytes.extend_from_slice(&[header.len() as u8, 0]);
+            bytes.extend_from_slice(header.as_bytes());
+            bytes.extend_from_slice(&[0; 16]);
+            assert_eq!(read_npy(&mut &bytes[..]).unwrap_err().kind(), kind);
+        }
+    }
+}
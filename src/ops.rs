@@ -0,0 +1,376 @@
+//! Operations on signed distance functions.
+use super::signed_scaled;
+use {DualNorm, EuclideanNorm};
+
+/// Turns the signed distance function of a region into the signed distance function of its
+/// complement, in place.
+///
+/// The zero level set is unchanged, only the inside and the outside swap. The "no interface"
+/// sentinels `±std::f64::MAX` are mapped to `∓std::f64::MAX`, that is, a field that is
+/// everywhere outside becomes everywhere inside and vice versa; infinities and `NaN` are
+/// preserved in the same way.
+pub fn complement(d: &mut [f64]) {
+    for d in d.iter_mut() {
+        *d = -*d;
+    }
+}
+
+/// Histogram equalization of a distance function for visualization.
+///
+/// The magnitudes `|d|` of the finite values are replaced by their rank among all finite
+/// magnitudes, scaled to `[0, 1]`, so that they are uniformly distributed and the fine detail
+/// near the interface gets as much of the color range as the far field. Equal magnitudes get the
+/// same (average) rank. The sign is kept, so the result is in `[-1, 1]` and the interface is
+/// still at zero.
+///
+/// Infinite values are mapped to `±1`, `NaN` stays `NaN`.
+pub fn equalize(d: &[f64], out: &mut [f64]) {
+    assert_eq!(d.len(), out.len());
+
+    let mut idx: Vec<usize> = (0..d.len()).filter(|&i| d[i].is_finite()).collect();
+    idx.sort_by(|&a, &b| d[a].abs().partial_cmp(&d[b].abs()).unwrap());
+    let scale = if idx.len() > 1 {
+        1. / (idx.len() - 1) as f64
+    } else {
+        0.
+    };
+
+    let mut start = 0;
+    while start < idx.len() {
+        let m = d[idx[start]].abs();
+        let mut end = start + 1;
+        while end < idx.len() && d[idx[end]].abs() == m {
+            end += 1;
+        }
+        let rank = 0.5 * (start + end - 1) as f64 * scale;
+        for &i in &idx[start..end] {
+            out[i] = if d[i] < 0. { -rank } else { rank };
+        }
+        start = end;
+    }
+
+    for (o, &d) in out.iter_mut().zip(d) {
+        if d.is_nan() {
+            *o = d;
+        } else if d.is_infinite() {
+            *o = d.signum();
+        }
+    }
+}
+
+/// Maps the signed distance function to the diffuse interface profile
+/// `φ = tanh(d / (√2 ε))` of the width `ε`, the equilibrium profile of the Allen-Cahn equation
+/// used by phase-field models.
+///
+/// `φ` goes from `-1` inside to `1` outside; between the distances `±√2 ε atanh(0.9) ≈ ±2.08 ε`
+/// it goes from `-0.9` to `0.9`. The "no interface" sentinels become `±1`.
+pub fn diffuse_interface(d: &[f64], epsilon: f64, out: &mut [f64]) {
+    assert_eq!(d.len(), out.len());
+    assert!(epsilon > 0., "The width must be positive, was {}", epsilon);
+
+    let s = 1. / (2f64.sqrt() * epsilon);
+    for (o, &d) in out.iter_mut().zip(d) {
+        *o = (d * s).tanh();
+    }
+}
+
+/// Converts the signed distance function on a regular 2D grid with spacing `h` to the coverage of
+/// the cells, that is, the fraction of the cell of the size `h` around each node that lies inside
+/// the region, for anti-aliased rendering.
+///
+/// The coverage is `0.5 - d / h` clamped to `[0, 1]`: `1` deep inside, `0` far outside and `0.5`
+/// for a node on the interface. This is exact for an interface parallel to the grid lines.
+pub fn coverage_from_sdf(d: &[f64], dim: (usize, usize), h: f64) -> Vec<f64> {
+    assert_eq!(dim.0 * dim.1, d.len());
+    d.iter().map(|&d| (0.5 - d / h).max(0.).min(1.)).collect()
+}
+
+/// Removes the spurious local minima of the magnitude of the signed distance function `d` on a
+/// regular 2D grid with spacing `h`, in place, so that it can be used as a potential field.
+/// Returns the number of the repaired nodes.
+///
+/// The exact distance function has no local minima away from the interface, so descending `|d|`
+/// always leads to the interface, but a field modified after the sweep (or corrupted) might. The
+/// _interface nodes_ are the nodes with `d = 0` or with a node of the opposite sign in their 3×3
+/// neighborhood; they are never changed. A spurious minimum is any other node whose `|d|` is at
+/// most that of all its horizontal and vertical neighbors. The spurious minima are recomputed
+/// from their neighbors by the Euclidean upwind update until they are consistent with them, and
+/// this is repeated until no minima remain. The sign of every node is kept. The nodes with the
+/// value `±std::f64::MAX` are not reached from the interface and are not minima, so a plateau of
+/// them is left as it is.
+///
+/// The output of [`signed_distance_2d`](../fn.signed_distance_2d.html) has no spurious minima.
+/// Nothing is changed if there is no interface.
+pub fn repair_local_minima(d: &mut [f64], dim: (usize, usize), h: f64) -> usize {
+    let (ni, nj) = dim;
+    assert_eq!(ni * nj, d.len());
+
+    let interface = interface_nodes_3x3(d, dim);
+    if !interface.contains(&true) {
+        return 0;
+    }
+
+    let mut a: Vec<f64> = d.iter().map(|d| (d.abs() / h).min(std::f64::MAX)).collect();
+    let mut repaired = vec![false; ni * nj];
+    let mut count = 0;
+    loop {
+        let minima: Vec<usize> = (0..ni * nj)
+            .filter(|&s| !interface[s] && a[s] < std::f64::MAX && is_local_min(&a, dim, s))
+            .collect();
+        if minima.is_empty() {
+            break;
+        }
+        for &s in &minima {
+            if !repaired[s] {
+                repaired[s] = true;
+                count += 1;
+            }
+            a[s] = std::f64::MAX;
+        }
+        // Gauss-Seidel iterations on the reset nodes only
+        loop {
+            let mut changed = false;
+            for &s in minima.iter().chain(minima.iter().rev()) {
+                let t = upwind_update(&a, dim, s);
+                if t < a[s] {
+                    a[s] = t;
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+    }
+
+    for s in 0..ni * nj {
+        if repaired[s] {
+            d[s] = signed_scaled(a[s], d[s] < 0., h);
+        }
+    }
+    count
+}
+
+/// Marks the nodes with `d = 0` or with a node of the opposite sign in their 3×3 neighborhood.
+fn interface_nodes_3x3(d: &[f64], dim: (usize, usize)) -> Vec<bool> {
+    let (ni, nj) = dim;
+    let mut interface = vec![false; ni * nj];
+    for i in 0..ni {
+        for j in 0..nj {
+            let s = i * nj + j;
+            let neg = d[s] < 0.;
+            interface[s] = d[s] == 0.
+                || (i.saturating_sub(1)..std::cmp::min(i + 2, ni)).any(|a| {
+                    (j.saturating_sub(1)..std::cmp::min(j + 2, nj))
+                        .any(|b| (d[a * nj + b] < 0.) != neg)
+                });
+        }
+    }
+    interface
+}
+
+/// Whether no horizontal or vertical neighbor of the node `s` has a smaller value.
+fn is_local_min(a: &[f64], dim: (usize, usize), s: usize) -> bool {
+    let (ni, nj) = dim;
+    let (i, j) = (s / nj, s % nj);
+    (i == 0 || a[s - nj] >= a[s])
+        && (i + 1 == ni || a[s + nj] >= a[s])
+        && (j == 0 || a[s - 1] >= a[s])
+        && (j + 1 == nj || a[s + 1] >= a[s])
+}
+
+/// The smallest of the Euclidean upwind updates of the node `s` from the four quadrants.
+fn upwind_update(a: &[f64], dim: (usize, usize), s: usize) -> f64 {
+    let (ni, nj) = dim;
+    let (i, j) = (s / nj, s % nj);
+    let max = std::f64::MAX;
+    let vi = [
+        if i > 0 { a[s - nj] } else { max },
+        if i + 1 < ni { a[s + nj] } else { max },
+    ];
+    let vj = [
+        if j > 0 { a[s - 1] } else { max },
+        if j + 1 < nj { a[s + 1] } else { max },
+    ];
+    let mut t = a[s];
+    for (&vi, si) in vi.iter().zip(&[1., -1.]) {
+        for (&vj, sj) in vj.iter().zip(&[1., -1.]) {
+            t = EuclideanNorm.inv_dual_norm(t, [vi, vj], [*si, *sj]);
+        }
+    }
+    t
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn equalize_is_uniform_and_monotone() {
+        let d: Vec<f64> = (0..101)
+            .map(|k| {
+                let x = k as f64 / 100.;
+                // strongly clustered near zero
+                if k % 2 == 0 {
+                    x.powi(4)
+                } else {
+                    -x.powi(3)
+                }
+            })
+            .collect();
+        let mut out = vec![0.; d.len()];
+        equalize(&d, &mut out);
+
+        let mut mags: Vec<f64> = out.iter().map(|o| o.abs()).collect();
+        mags.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        for (k, &m) in mags.iter().enumerate() {
+            assert!((m - k as f64 / 100.).abs() < 1e-12);
+        }
+        for a in 0..d.len() {
+            assert_eq!(out[a] < 0., d[a] < 0.);
+            for b in 0..d.len() {
+                if d[a].abs() < d[b].abs() {
+                    assert!(out[a].abs() < out[b].abs());
+                }
+            }
+        }
+
+        let d = [1., 1., -3., std::f64::INFINITY, std::f64::NAN];
+        let mut out = [0.; 5];
+        equalize(&d, &mut out);
+        assert_eq!(&out[..4], &[0.25, 0.25, -1., 1.]);
+        assert!(out[4].is_nan());
+    }
+
+    #[test]
+    fn diffuse_profile_width() {
+        let eps = 0.05;
+        let w = 2f64.sqrt() * eps * 0.9f64.atanh();
+        let d = [-std::f64::MAX, -w, -0.01, 0., 0.01, w, 1., std::f64::MAX];
+        let mut phi = [0.; 8];
+        diffuse_interface(&d, eps, &mut phi);
+        assert_eq!(phi[0], -1.);
+        assert!((phi[1] + 0.9).abs() < 1e-12);
+        assert_eq!(phi[3], 0.);
+        assert!((phi[5] - 0.9).abs() < 1e-12);
+        assert!(phi[6] > 1. - 1e-9);
+        assert_eq!(phi[7], 1.);
+        for p in phi.windows(2) {
+            assert!(p[0] < p[1]);
+        }
+    }
+
+    #[test]
+    fn coverage_of_circle() {
+        let n = 33;
+        let h = 1. / (n - 1) as f64;
+        let mut u = vec![0.; n * n];
+        for i in 0..n {
+            for j in 0..n {
+                let (x, y) = (i as f64 * h - 0.5, j as f64 * h - 0.5);
+                u[i * n + j] = x.hypot(y) - 0.3;
+            }
+        }
+        let mut d = vec![0.; n * n];
+        ::signed_distance_2d(&mut d, &u, (n, n), h);
+        let c = coverage_from_sdf(&d, (n, n), h);
+        for s in 0..n * n {
+            if u[s] < -h {
+                assert_eq!(c[s], 1.);
+            } else if u[s] > h {
+                assert_eq!(c[s], 0.);
+            } else if u[s].abs() < 0.1 * h {
+                assert!((c[s] - 0.5).abs() < 0.15, "{}", c[s]);
+            }
+        }
+        // across a straight interface the coverage drops from 1 to 0 within one cell
+        let d = [-h, -0.5 * h, 0., 0.5 * h, h];
+        assert_eq!(coverage_from_sdf(&d, (5, 1), h), [1., 1., 0.5, 0., 0.]);
+    }
+
+    #[test]
+    fn complement_is_involution() {
+        let orig = [
+            -1.5,
+            0.,
+            0.25,
+            std::f64::MAX,
+            -std::f64::MAX,
+            std::f64::INFINITY,
+        ];
+        let mut d = orig;
+        complement(&mut d);
+        assert_eq!(
+            d,
+            [
+                1.5,
+                0.,
+                -0.25,
+                -std::f64::MAX,
+                std::f64::MAX,
+                -std::f64::INFINITY
+            ]
+        );
+        complement(&mut d);
+        assert_eq!(d, orig);
+    }
+
+    #[test]
+    fn repair_removes_spurious_minimum() {
+        let n = 41;
+        let h = 1. / (n - 1) as f64;
+        let mut u = vec![0.; n * n];
+        for i in 0..n {
+            for j in 0..n {
+                let (x, y) = (i as f64 * h - 0.5, j as f64 * h - 0.5);
+                u[i * n + j] = x.hypot(y) - 0.2;
+            }
+        }
+        let mut orig = vec![0.; n * n];
+        ::signed_distance_2d(&mut orig, &u, (n, n), h);
+        let mut d = orig.clone();
+        assert_eq!(repair_local_minima(&mut d, (n, n), h), 0);
+        assert_eq!(d, orig);
+
+        // an artificial basin far outside of the circle
+        for i in 3..6 {
+            for j in 3..6 {
+                d[i * n + j] = 0.01;
+            }
+        }
+        let interface = interface_nodes_3x3(&d, (n, n));
+        assert_eq!(repair_local_minima(&mut d, (n, n), h), 9);
+
+        let a: Vec<f64> = d.iter().map(|d| d.abs()).collect();
+        for s in 0..n * n {
+            assert_eq!(d[s] < 0., orig[s] < 0.);
+            if interface[s] {
+                assert_eq!(d[s], orig[s]);
+            } else {
+                assert!(!is_local_min(&a, (n, n), s), "minimum at {}", s);
+            }
+            assert!((d[s] - orig[s]).abs() < 0.1 * h, "{} != {}", d[s], orig[s]);
+        }
+    }
+
+    #[test]
+    fn repair_skips_unreachable_plateau() {
+        let n = 9;
+        let h = 0.5;
+        let mut d = vec![0.; n * n];
+        for i in 0..n {
+            for j in 0..n {
+                d[i * n + j] = (i as f64 - 1.5) * h;
+            }
+        }
+        // a block of sentinels at the edge, the inner ones without a finite neighbor
+        for i in 6..9 {
+            for j in 3..6 {
+                d[i * n + j] = std::f64::MAX;
+            }
+        }
+        let orig = d.clone();
+        assert_eq!(repair_local_minima(&mut d, (n, n), h), 0);
+        assert_eq!(d, orig);
+    }
+}
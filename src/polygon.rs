@@ -0,0 +1,133 @@
+//! Signed distance functions of polygons given by their outlines.
+//!
+//! The coordinates are physical, the node `(i, j)` being at `(i h, j h)`.
+use super::signed_scaled;
+use contour::segment_dist;
+use eikonal::fast_sweep_2d;
+use norm::{DualNorm, EuclideanNorm};
+
+/// Computes the signed distance from the outlines of the closed `polygons` on a regular 2D grid
+/// of dimensions `dim` with spacing `h`, negative inside.
+///
+/// The last vertex of every polygon is connected to the first one. The nodes within one cell of
+/// an edge get their exact distance from the nearest edge, and the distance is then propagated by
+/// the sweeps like in [`signed_distance_2d`](../fn.signed_distance_2d.html), so no level set
+/// function is rasterized. A node is inside if the sum of the winding numbers of all polygons
+/// around it is nonzero: the polygons may overlap, and a hole is a polygon with the orientation
+/// opposite to the one around it. The nodes exactly on an edge may fall on either side.
+///
+/// The nodes are `std::f64::MAX` if there are no edges.
+pub fn signed_distance_polygon(
+    d: &mut [f64],
+    polygons: &[Vec<(f64, f64)>],
+    dim: (usize, usize),
+    h: f64,
+) {
+    let (ni, nj) = dim;
+    assert_eq!(ni * nj, d.len());
+    assert!(ni >= 1 && nj >= 1, "The grid is empty");
+
+    for d in &mut *d {
+        *d = std::f64::MAX;
+    }
+    // the winding numbers, first as the differences along the rows
+    let mut wind = vec![0i32; ni * nj];
+    let range = |a: f64, b: f64, n: usize| {
+        // the nodes at most one cell beyond the bounding box, as a range of indices
+        let lo = ((a.min(b) / h).floor() - 1.).max(0.);
+        let hi = ((a.max(b) / h).ceil() + 2.).max(0.).min(n as f64);
+        (lo as usize, hi as usize)
+    };
+    for polygon in polygons {
+        if polygon.is_empty() {
+            continue;
+        }
+        let mut a = polygon[polygon.len() - 1];
+        for &b in polygon {
+            // the exact distance in the cells around the edge, in grid units
+            let (i0, i1) = range(a.0, b.0, ni);
+            let (j0, j1) = range(a.1, b.1, nj);
+            for i in i0..i1 {
+                for j in j0..j1 {
+                    let p = [i as f64 * h, j as f64 * h];
+                    let e = segment_dist(p, [a.0, a.1], [b.0, b.1]) / h;
+                    let s = i * nj + j;
+                    if e < d[s] {
+                        d[s] = e;
+                    }
+                }
+            }
+
+            // the edge winds around the nodes of the rows it crosses below the crossing
+            let dir = if b.0 > a.0 { 1 } else { -1 };
+            for i in i0..i1 {
+                let x = i as f64 * h;
+                if (a.0 > x) != (b.0 > x) {
+                    let y = a.1 + (x - a.0) * (b.1 - a.1) / (b.0 - a.0);
+                    let k = (y / h).ceil().max(0.).min(nj as f64) as usize;
+                    wind[i * nj] += dir;
+                    if k < nj {
+                        wind[i * nj + k] -= dir;
+                    }
+                }
+            }
+            a = b;
+        }
+    }
+    for row in wind.chunks_mut(nj) {
+        for j in 1..nj {
+            row[j] += row[j - 1];
+        }
+    }
+
+    fast_sweep_2d(d, dim, |d, v, s| EuclideanNorm.inv_dual_norm(d, v, s));
+    for (d, &w) in d.iter_mut().zip(&wind) {
+        *d = signed_scaled(*d, w != 0, h);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rectangle_with_hole() {
+        let n = 41;
+        let h = 0.025;
+        let outer = vec![(0.21, 0.33), (0.79, 0.33), (0.79, 0.68), (0.21, 0.68)];
+        // the opposite orientation
+        let hole = vec![(0.41, 0.46), (0.41, 0.56), (0.61, 0.56), (0.61, 0.46)];
+        let polygons = vec![outer, hole];
+        let mut d = vec![0.; n * n];
+        signed_distance_polygon(&mut d, &polygons, (n, n), h);
+
+        let inside = |x: f64, y: f64| {
+            let in_rect =
+                |r: &Vec<(f64, f64)>| x > r[0].0 && x < r[2].0 && y > r[0].1 && y < r[2].1;
+            in_rect(&polygons[0]) && !in_rect(&polygons[1])
+        };
+        for i in 0..n {
+            for j in 0..n {
+                let p = [i as f64 * h, j as f64 * h];
+                let mut e = std::f64::MAX;
+                for polygon in &polygons {
+                    let mut a = polygon[polygon.len() - 1];
+                    for &b in polygon {
+                        e = e.min(segment_dist(p, [a.0, a.1], [b.0, b.1]));
+                        a = b;
+                    }
+                }
+                let s = i * n + j;
+                assert_eq!(d[s] < 0., inside(p[0], p[1]), "sign at ({}, {})", i, j);
+                if e <= h {
+                    assert!((d[s].abs() - e).abs() < 1e-12, "{} != {}", d[s], e);
+                } else {
+                    assert!((d[s].abs() - e).abs() < h, "{} != {}", d[s], e);
+                }
+            }
+        }
+
+        signed_distance_polygon(&mut d, &[], (n, n), h);
+        assert!(d.iter().all(|&d| d == std::f64::MAX));
+    }
+}
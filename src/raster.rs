@@ -0,0 +1,86 @@
+//! Signed distance of masks given as grayscale images, and rendering of distance functions back
+//! to images for inspection.
+//!
+//! The node `(i, j)` is the pixel in the row `i` and the column `j`, that is, at `x = j` and
+//! `y = i` in the coordinates of the image, and the dimensions of the grid are
+//! `(height, width)`. With this convention the pixels of a `GrayImage` are the values of the grid
+//! in the row-major order of this crate, and the distance of a node is measured between the
+//! pixel centers.
+//!
+//! Requires the `image` feature.
+use distance_from_mask;
+use image::GrayImage;
+
+/// Computes the signed distance from the boundary of the bright part of `img`, the pixels with
+/// the luma at least `threshold`, on a grid with spacing `h` per pixel.
+///
+/// The distance is negative at the bright pixels and is computed by
+/// [`distance_from_mask`](../fn.distance_from_mask.html), so the boundary passes halfway between
+/// the neighboring pixels on the opposite sides of the threshold. The result has the dimensions
+/// `(img.height(), img.width())`; the values are `±std::f64::MAX` if all pixels are on the same
+/// side.
+pub fn signed_distance_from_luma(img: &GrayImage, threshold: u8, h: f64) -> Vec<f64> {
+    let (width, height) = img.dimensions();
+    let dim = (height as usize, width as usize);
+    let mask: Vec<bool> = img.iter().map(|&luma| luma >= threshold).collect();
+    let mut d = vec![0.; dim.0 * dim.1];
+    distance_from_mask(&mut d, &mask, dim, h);
+    d
+}
+
+/// Renders the distance function `d` with dimensions `dim` as a grayscale image of width `dim.1`
+/// and height `dim.0`.
+///
+/// The values are normalized by the largest finite magnitude `m` in `d`, ignoring the
+/// `±std::f64::MAX` sentinels: the distance `-m` (deep inside) is white, `m` is black, and the
+/// zero level set is at the middle gray, so that
+/// [`signed_distance_from_luma`](fn.signed_distance_from_luma.html) with the threshold 128
+/// recovers the inside.
+pub fn luma_from_distance(d: &[f64], dim: (usize, usize)) -> GrayImage {
+    assert_eq!(dim.0 * dim.1, d.len());
+    let m = d
+        .iter()
+        .filter(|d| d.abs() < std::f64::MAX)
+        .fold(0., |m: f64, d| m.max(d.abs()));
+    let m = if m > 0. { m } else { 1. };
+    let pixels = d
+        .iter()
+        .map(|&d| (127.5 - 127.5 * d / m).max(0.).min(255.).round() as u8)
+        .collect();
+    GrayImage::from_raw(dim.1 as u32, dim.0 as u32, pixels).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Luma;
+
+    #[test]
+    fn disk_from_image_and_back() {
+        let (width, height) = (40, 30);
+        let (cx, cy, r) = (22.3, 13.6, 8.2);
+        let img = GrayImage::from_fn(width, height, |x, y| {
+            let inside = (x as f64 - cx).hypot(y as f64 - cy) < r;
+            Luma([if inside { 200 } else { 30 }])
+        });
+        let h = 0.5;
+        let d = signed_distance_from_luma(&img, 128, h);
+        assert_eq!(d.len(), (width * height) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let s = (y * width + x) as usize;
+                assert_eq!(d[s] < 0., img.get_pixel(x, y)[0] >= 128);
+                // the boundary is within a pixel of the circle
+                let e = ((x as f64 - cx).hypot(y as f64 - cy) - r) * h;
+                assert!((d[s] - e).abs() < 1.5 * h, "{} != {}", d[s], e);
+            }
+        }
+
+        let out = luma_from_distance(&d, (height as usize, width as usize));
+        assert_eq!(out.dimensions(), (width, height));
+        for (p, q) in out.iter().zip(img.iter()) {
+            assert_eq!(*p >= 128, *q >= 128);
+        }
+        assert_eq!(signed_distance_from_luma(&out, 128, h), d);
+    }
+}
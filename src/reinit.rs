@@ -0,0 +1,185 @@
+//! Redistancing of evolving level sets.
+//!
+//! In the level set method, the level set function is periodically replaced by the signed distance
+//! function to its zero level set. The functions in this module help to keep the result consistent
+//! between the successive time steps.
+use super::{signed_from_eikonal, signed_scaled};
+use level_set::init_dist_2d;
+use {eikonal, DualNorm, EuclideanNorm};
+
+/// Computes the signed distance like [`signed_distance_2d`](../fn.signed_distance_2d.html), biased
+/// towards the signed distance function `prev` from the previous time step (frame).
+///
+/// Where the new initial distance near the level set differs from `prev` by at most `tol`, the
+/// previous value (including its sign) is kept instead. This suppresses the flickering of the
+/// interface due to small perturbations of `u`, at the cost of an additional error of at most
+/// `tol` near the interface. Larger motions of the interface are followed without any bias.
+///
+/// This does not speed up the computation.
+pub fn signed_distance_2d_coherent(
+    d: &mut [f64],
+    u: &[f64],
+    prev: &[f64],
+    dim: (usize, usize),
+    h: f64,
+    tol: f64,
+) {
+    assert_eq!(dim.0 * dim.1, u.len());
+    assert_eq!(dim.0 * dim.1, d.len());
+    assert_eq!(dim.0 * dim.1, prev.len());
+
+    init_dist_2d(d, u, dim, |p| EuclideanNorm.dual_norm(p));
+
+    let mut sign: Vec<f64> = u.iter().map(|&u| if u < 0. { -1. } else { 1. }).collect();
+    for i in 0..d.len() {
+        if d[i] < std::f64::MAX && (sign[i] * d[i] * h - prev[i]).abs() <= tol {
+            d[i] = prev[i].abs() / h;
+            sign[i] = if prev[i] < 0. { -1. } else { 1. };
+        }
+    }
+
+    eikonal::fast_sweep_2d(d, dim, |d, v, s| EuclideanNorm.inv_dual_norm(d, v, s));
+
+    for i in 0..d.len() {
+        d[i] = signed_scaled(d[i], sign[i] < 0., h);
+    }
+}
+
+/// Replaces the values of `d` on a regular 2D grid with spacing `h` by the signed distance from
+/// their own _zero_ level set, without moving it.
+///
+/// The sign of `d` is kept. The nodes of the triangles crossed by the level set get the distance
+/// from the linear interpolation of `d` on the triangles, and these values are kept fixed during
+/// the sweeps: only the nodes further away are recomputed. The sweeps are repeated until nothing
+/// changes.
+pub fn reinitialize(d: &mut [f64], dim: (usize, usize), h: f64) {
+    assert_eq!(dim.0 * dim.1, d.len());
+
+    let u = d.to_vec();
+    init_dist_2d(d, &u, dim, |p| EuclideanNorm.dual_norm(p));
+    let fixed: Vec<bool> = d.iter().map(|&d| d < std::f64::MAX).collect();
+    eikonal::fast_sweep_dist_2d_frozen(d, &fixed, dim);
+
+    signed_from_eikonal(d, &u, h);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use signed_distance_2d;
+
+    /// Maximal change between consecutive frames.
+    fn variation(frames: &[Vec<f64>]) -> f64 {
+        frames
+            .windows(2)
+            .map(|w| {
+                w[0].iter()
+                    .zip(&w[1])
+                    .fold(0f64, |m, (a, b)| m.max((a - b).abs()))
+            })
+            .fold(0., f64::max)
+    }
+
+    #[test]
+    fn coherent_reduces_flicker() {
+        let n = 33;
+        let h = 1. / (n - 1) as f64;
+        let mut seed = 12345u64;
+        let mut noise = || {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((seed >> 11) as f64 / (1u64 << 53) as f64 - 0.5) * 2e-3
+        };
+
+        let mut independent = vec![];
+        let mut coherent: Vec<Vec<f64>> = vec![];
+        for _ in 0..5 {
+            let mut u = vec![0.; n * n];
+            for i in 0..n {
+                for j in 0..n {
+                    let (x, y) = (i as f64 * h - 0.5, j as f64 * h - 0.45);
+                    u[i * n + j] = x.hypot(y) - 0.3 + noise();
+                }
+            }
+            let mut d = vec![0.; n * n];
+            signed_distance_2d(&mut d, &u, (n, n), h);
+            if coherent.is_empty() {
+                coherent.push(d.clone());
+            } else {
+                let mut c = vec![0.; n * n];
+                signed_distance_2d_coherent(&mut c, &u, coherent.last().unwrap(), (n, n), h, 0.01);
+                coherent.push(c);
+            }
+            independent.push(d);
+        }
+
+        let (vi, vc) = (variation(&independent), variation(&coherent));
+        assert!(vi > 1e-4, "no flicker to reduce: {}", vi);
+        assert!(vc < 0.1 * vi, "independent: {}, coherent: {}", vi, vc);
+    }
+
+    #[test]
+    fn coherent_keeps_sentinel() {
+        let (u, prev) = (vec![1.; 16], vec![0.; 16]);
+        let mut d = vec![0.; 16];
+        signed_distance_2d_coherent(&mut d, &u, &prev, (4, 4), 2., 0.1);
+        assert!(d.iter().all(|&d| d == std::f64::MAX));
+    }
+
+    #[test]
+    fn reinitialize_keeps_interface() {
+        let n = 33;
+        let h = 1. / (n - 1) as f64;
+        let mut exact = vec![0.; n * n];
+        let mut d = vec![0.; n * n];
+        for i in 0..n {
+            for j in 0..n {
+                let (x, y) = (i as f64 * h - 0.5, j as f64 * h - 0.45);
+                exact[i * n + j] = x.hypot(y) - 0.3;
+                // the same zero level set, but far from a distance function
+                d[i * n + j] = exact[i * n + j] * (1.5 + x) * (1.5 + x);
+            }
+        }
+        let before = d.clone();
+        let mut near = vec![0.; n * n];
+        init_dist_2d(&mut near, &before, (n, n), |p| EuclideanNorm.dual_norm(p));
+
+        reinitialize(&mut d, (n, n), h);
+        for s in 0..n * n {
+            assert_eq!(d[s] < 0., before[s] < 0.);
+            if near[s] < std::f64::MAX {
+                assert_eq!(d[s].abs(), near[s] * h);
+            }
+            assert!((d[s] - exact[s]).abs() < 2. * h, "{} != {}", d[s], exact[s]);
+        }
+    }
+
+    #[test]
+    fn reinitialize_preserves_lines() {
+        let n = 17;
+        let h = 1. / (n - 1) as f64;
+        for k in 0..12 {
+            let (gy, gx) = (0.3 + k as f64 * 0.5).sin_cos();
+            let c = -(gx + gy) * 0.5;
+            let u: Vec<f64> = (0..n * n)
+                .map(|s| (s / n) as f64 * h * gx + (s % n) as f64 * h * gy + c)
+                .collect();
+            let mut near = vec![0.; n * n];
+            init_dist_2d(&mut near, &u, (n, n), |p| EuclideanNorm.dual_norm(p));
+
+            let mut d = u.clone();
+            reinitialize(&mut d, (n, n), h);
+            let first = d.clone();
+            reinitialize(&mut d, (n, n), h);
+            for s in 0..n * n {
+                // the frozen nodes have the exact distance from the line
+                if near[s] < std::f64::MAX {
+                    assert!((first[s] - u[s]).abs() < 1e-12, "{} != {}", first[s], u[s]);
+                }
+                // so the line does not move when repeated
+                assert!((d[s] - first[s]).abs() < 1e-12, "{} != {}", d[s], first[s]);
+            }
+        }
+    }
+}
@@ -0,0 +1,103 @@
+//! Level set functions from scattered data.
+//!
+//! When the level set function is known only at scattered points, for instance from sensors, it
+//! is first interpolated onto the grid and the signed distance is then computed from the grid
+//! values as usual.
+//!
+//! The coordinates are physical, the node `(i, j)` being at `origin + (i h, j h)`.
+
+/// Interpolates the values of the scattered `points`, given as `(x, y, value)`, onto a regular 2D
+/// grid of dimensions `dim` by inverse distance weighting (Shepard's method).
+///
+/// The value at a node is `Σ wₖ vₖ / Σ wₖ` with the weights `wₖ = 1 / rₖ^power`, where `rₖ` is
+/// the distance of the point `k` from the node. A node that coincides with a point (up to
+/// `1e-9 h`) takes its value, or the average of the values if there are several such points.
+/// Larger `power` makes the result more local; in 2D, `power > 2` is needed for the far points
+/// not to dominate on large point sets.
+///
+/// The result can be passed as `u` to [`signed_distance_2d`](../fn.signed_distance_2d.html).
+pub fn grid_from_scattered(
+    points: &[(f64, f64, f64)],
+    dim: (usize, usize),
+    h: f64,
+    origin: (f64, f64),
+    power: f64,
+) -> Vec<f64> {
+    let (ni, nj) = dim;
+    assert!(!points.is_empty(), "At least one point is needed");
+    assert!(power > 0., "The power must be positive, was {}", power);
+
+    let eps2 = (1e-9 * h) * (1e-9 * h);
+    let mut u = vec![0.; ni * nj];
+    for i in 0..ni {
+        for j in 0..nj {
+            let (x, y) = (origin.0 + i as f64 * h, origin.1 + j as f64 * h);
+            let (mut sum, mut weight) = (0., 0.);
+            let (mut on_node, mut count) = (0., 0);
+            for &(px, py, v) in points {
+                let r2 = (px - x) * (px - x) + (py - y) * (py - y);
+                if r2 <= eps2 {
+                    on_node += v;
+                    count += 1;
+                } else if count == 0 {
+                    let w = r2.powf(-0.5 * power);
+                    sum += w * v;
+                    weight += w;
+                }
+            }
+            u[i * nj + j] = if count > 0 {
+                on_node / count as f64
+            } else {
+                sum / weight
+            };
+        }
+    }
+    u
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use signed_distance_2d;
+
+    #[test]
+    fn plane_from_scattered_points() {
+        let n = 21;
+        let h = 0.05;
+        let origin = (-0.5, -0.5);
+        let plane = |x: f64, y: f64| 0.6 * x + 0.8 * y - 0.1;
+
+        // points in the centers of the cells, reaching 6 cells beyond the grid
+        let mut points = vec![];
+        for a in -6..n as isize + 6 {
+            for b in -6..n as isize + 6 {
+                let x = origin.0 + (a as f64 + 0.5) * h;
+                let y = origin.1 + (b as f64 + 0.5) * h;
+                points.push((x, y, plane(x, y)));
+            }
+        }
+        let u = grid_from_scattered(&points, (n, n), h, origin, 4.);
+        let mut d = vec![0.; n * n];
+        signed_distance_2d(&mut d, &u, (n, n), h);
+        for i in 0..n {
+            for j in 0..n {
+                let s = i * n + j;
+                let (x, y) = (origin.0 + i as f64 * h, origin.1 + j as f64 * h);
+                let e = plane(x, y);
+                assert!((u[s] - e).abs() < 0.1 * h, "u {} != {}", u[s], e);
+                // the distance is to the part of the line inside the grid
+                let (px, py) = (x - 0.6 * e, y - 0.8 * e);
+                let l = (n - 1) as f64 * h;
+                if px >= origin.0 && px <= origin.0 + l && py >= origin.1 && py <= origin.1 + l {
+                    assert!((d[s] - e).abs() < 0.25 * h, "d {} != {}", d[s], e);
+                }
+            }
+        }
+
+        // a point on a node gives the node its value exactly
+        points.push((origin.0 + 3. * h, origin.1 + 4. * h, 7.));
+        let u = grid_from_scattered(&points, (n, n), h, origin, 4.);
+        assert_eq!(u[3 * n + 4], 7.);
+        assert!(u.iter().all(|u| u.is_finite()));
+    }
+}
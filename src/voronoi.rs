@@ -0,0 +1,151 @@
+//! Geodesic Voronoi diagrams: the nearest of a set of labeled seeds in a metric given by a speed
+//! and obstacles.
+use super::min;
+use eikonal;
+use error::{Preflight, SweepError};
+
+/// Computes the first arrival times `time` from the `seeds` on a regular 2D grid with spacing `h`
+/// and labels every node with the label of the seed that reaches it first.
+///
+/// The seeds are given as `(i, j, label)`. The front moves with the speed `speed` at each node,
+/// so the arrival time solves the eikonal equation `speed |∇t| = 1`. The nodes marked in `blocked`
+/// are obstacles which the front cannot cross; the travel times go around them.
+///
+/// The label of a node is taken from the upwind neighbor with the smaller arrival time. The nodes
+/// that cannot be reached from any seed (including the blocked nodes) get the time
+/// `std::f64::MAX` and the label `std::u32::MAX`.
+///
+/// The sweeps in the four directions are repeated until the times do not change anymore.
+pub fn geodesic_voronoi(
+    label: &mut [u32],
+    time: &mut [f64],
+    seeds: &[(usize, usize, u32)],
+    speed: &[f64],
+    blocked: &[bool],
+    dim: (usize, usize),
+    h: f64,
+) {
+    let (ni, nj) = dim;
+    assert_eq!(ni * nj, label.len());
+    assert_eq!(ni * nj, time.len());
+    assert_eq!(ni * nj, speed.len());
+    assert_eq!(ni * nj, blocked.len());
+    for (&c, &b) in speed.iter().zip(blocked) {
+        assert!(b || c > 0., "The speed must be positive, was {}", c);
+    }
+
+    for (t, l) in time.iter_mut().zip(label.iter_mut()) {
+        *t = std::f64::MAX;
+        *l = std::u32::MAX;
+    }
+    for &(i, j, l) in seeds {
+        assert!(
+            i < ni && j < nj,
+            "The seed ({}, {}) is outside the grid",
+            i,
+            j
+        );
+        let s = i * nj + j;
+        assert!(!blocked[s], "The seed ({}, {}) is blocked", i, j);
+        time[s] = 0.;
+        label[s] = l;
+    }
+
+    eikonal::sweep_2d_repeat(
+        time,
+        dim,
+        0.,
+        |s| blocked[s],
+        |time, s, (i, j), _| {
+            // the smaller neighbor along each axis; the blocked nodes stay at MAX
+            let axes = [(i > 0, i + 1 < ni, nj), (j > 0, j + 1 < nj, 1)];
+            let mut up = [(std::f64::MAX, s); 2];
+            for (u, &(has_lo, has_hi, stride)) in up.iter_mut().zip(&axes) {
+                if has_lo && time[s - stride] < u.0 {
+                    *u = (time[s - stride], s - stride);
+                }
+                if has_hi && time[s + stride] < u.0 {
+                    *u = (time[s + stride], s + stride);
+                }
+            }
+            let ((a, ka), (b, kb)) = (up[0], up[1]);
+            if a == std::f64::MAX && b == std::f64::MAX {
+                return time[s];
+            }
+            let f = h / speed[s];
+            let t = if (a - b).abs() >= f {
+                min(a, b) + f
+            } else {
+                0.5 * (a + b + (2. * f * f - (a - b) * (a - b)).sqrt())
+            };
+            if t < time[s] {
+                label[s] = label[if a <= b { ka } else { kb }];
+            }
+            t
+        },
+    );
+}
+
+/// Computes the geodesic Voronoi diagram like [`geodesic_voronoi`](fn.geodesic_voronoi.html), but
+/// returns an error instead of panicking on invalid input.
+///
+/// The lengths of the arrays must match `dim`, the speed must be positive and finite at the nodes
+/// that are not blocked and `h` positive. The seeds must lie on the grid and not be blocked.
+pub fn try_geodesic_voronoi(
+    label: &mut [u32],
+    time: &mut [f64],
+    seeds: &[(usize, usize, u32)],
+    speed: &[f64],
+    blocked: &[bool],
+    dim: (usize, usize),
+    h: f64,
+) -> Result<(), SweepError> {
+    Preflight::new(dim.0 * dim.1)
+        .len("label", label.len())?
+        .len("time", time.len())?
+        .len("blocked", blocked.len())?
+        .seeds(dim, seeds.iter().map(|&(i, j, _)| (i, j)), |s| blocked[s])?
+        .speed(speed, |s| blocked[s])?
+        .spacing(h)?;
+    geodesic_voronoi(label, time, seeds, speed, blocked, dim, h);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn boundary_bends_around_wall() {
+        let n = 41;
+        let h = 1. / (n - 1) as f64;
+        let seeds = [(10, 20, 1), (30, 20, 2)];
+        let speed = vec![1.; n * n];
+        let mut label = vec![0; n * n];
+        let mut time = vec![0.; n * n];
+
+        let open = vec![false; n * n];
+        geodesic_voronoi(&mut label, &mut time, &seeds, &speed, &open, (n, n), h);
+        assert_eq!(label[16 * n + 20], 1);
+        assert_eq!(label[24 * n + 20], 2);
+        assert!((time[16 * n + 20] - 6. * h).abs() < 1e-12);
+
+        // a wall at i = 14 with a gap at j > 34
+        let mut blocked = vec![false; n * n];
+        for j in 0..35 {
+            blocked[14 * n + j] = true;
+        }
+        geodesic_voronoi(&mut label, &mut time, &seeds, &speed, &blocked, (n, n), h);
+        // just behind the wall the second depot is closer now
+        assert_eq!(label[16 * n + 20], 2);
+        assert!((time[16 * n + 20] - 14. * h).abs() < 1e-12);
+        // but the first depot still wins next to the gap
+        assert_eq!(label[16 * n + 38], 1);
+        assert_eq!(label[14 * n + 20], std::u32::MAX);
+        assert_eq!(time[14 * n + 20], std::f64::MAX);
+        // the boundary moves towards the first depot away from the gap
+        let first_of_2 = |j: usize| (0..n).find(|&i| label[i * n + j] == 2).unwrap();
+        assert!(first_of_2(0) <= 15);
+        assert!(first_of_2(n - 1) > 18);
+    }
+}
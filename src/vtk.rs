@@ -0,0 +1,159 @@
+//! Export of distance fields to VTK image data files (`.vti`) for ParaView and similar tools.
+//!
+//! The files are written in the XML format with ASCII data, so they need no further dependencies
+//! but grow large for big grids. The node `(i, j)` (or `(i, j, k)`) is written at the point
+//! `(i h, j h)`, the same as everywhere in this crate: the first index is the `x` axis of VTK.
+//! Since VTK stores the points with `x` varying fastest, the values are transposed on the way
+//! out; writing the slice as is would swap the axes in the viewer.
+//!
+//! Requires the `vtk` feature.
+use derivatives::gradient;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// Writes the 2D distance field `d` with dimensions `dim` and spacing `h` to the file `path`.
+pub fn write_vtk<P: AsRef<Path>>(
+    path: P,
+    d: &[f64],
+    dim: (usize, usize),
+    h: f64,
+) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    write_vti(&mut out, d, dim, h, false)?;
+    out.flush()
+}
+
+/// Writes the 2D distance field `d` like [`write_vtk`](fn.write_vtk.html), together with its
+/// gradient computed by [`derivatives::gradient`](../derivatives/fn.gradient.html).
+pub fn write_vtk_with_gradient<P: AsRef<Path>>(
+    path: P,
+    d: &[f64],
+    dim: (usize, usize),
+    h: f64,
+) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    write_vti(&mut out, d, dim, h, true)?;
+    out.flush()
+}
+
+/// Writes the 3D distance field `d` with dimensions `dim` and spacing `h` to the file `path`.
+pub fn write_vtk_3d<P: AsRef<Path>>(
+    path: P,
+    d: &[f64],
+    dim: (usize, usize, usize),
+    h: f64,
+) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    write_image(&mut out, d, dim, h, None)?;
+    out.flush()
+}
+
+/// Writes the 2D distance field `d` as VTK image data to `out`, with the point data array
+/// `distance` and, if `with_gradient` is true, the 3-component array `gradient` with a zero `z`
+/// component.
+///
+/// The gradient needs at least 2 nodes along both axes.
+pub fn write_vti<W: Write>(
+    out: &mut W,
+    d: &[f64],
+    dim: (usize, usize),
+    h: f64,
+    with_gradient: bool,
+) -> io::Result<()> {
+    let g = if with_gradient {
+        Some(gradient(d, dim, h))
+    } else {
+        None
+    };
+    write_image(out, d, (dim.0, dim.1, 1), h, g)
+}
+
+/// Writes the image data of the 3D field `d`, with the optional 2D gradient `g`.
+fn write_image<W: Write>(
+    out: &mut W,
+    d: &[f64],
+    dim: (usize, usize, usize),
+    h: f64,
+    g: Option<(Vec<f64>, Vec<f64>)>,
+) -> io::Result<()> {
+    let (ni, nj, nk) = dim;
+    assert_eq!(ni * nj * nk, d.len());
+    assert!(ni > 0 && nj > 0 && nk > 0, "The grid must not be empty");
+    // the indices in `d` in the order of VTK, `x` (the first index) varying fastest
+    let order = || {
+        (0..nk).flat_map(move |k| {
+            (0..nj).flat_map(move |j| (0..ni).map(move |i| (i * nj + j) * nk + k))
+        })
+    };
+
+    let extent = format!("0 {} 0 {} 0 {}", ni - 1, nj - 1, nk - 1);
+    writeln!(out, r#"<?xml version="1.0"?>"#)?;
+    writeln!(
+        out,
+        r#"<VTKFile type="ImageData" version="0.1" byte_order="LittleEndian">"#
+    )?;
+    writeln!(
+        out,
+        r#"  <ImageData WholeExtent="{}" Origin="0 0 0" Spacing="{} {} {}">"#,
+        extent, h, h, h
+    )?;
+    writeln!(out, r#"    <Piece Extent="{}">"#, extent)?;
+    writeln!(out, r#"      <PointData Scalars="distance">"#)?;
+    writeln!(
+        out,
+        r#"        <DataArray type="Float64" Name="distance" format="ascii">"#
+    )?;
+    for s in order() {
+        writeln!(out, "{}", d[s])?;
+    }
+    writeln!(out, "        </DataArray>")?;
+    if let Some((gx, gy)) = g {
+        writeln!(
+            out,
+            r#"        <DataArray type="Float64" Name="gradient" NumberOfComponents="3" format="ascii">"#
+        )?;
+        for s in order() {
+            writeln!(out, "{} {} 0", gx[s], gy[s])?;
+        }
+        writeln!(out, "        </DataArray>")?;
+    }
+    writeln!(out, "      </PointData>")?;
+    writeln!(out, "    </Piece>")?;
+    writeln!(out, "  </ImageData>")?;
+    writeln!(out, "</VTKFile>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_index_is_x() {
+        let (ni, nj) = (3, 2);
+        // d = 10 i + j
+        let d: Vec<f64> = (0..ni * nj)
+            .map(|s| (10 * (s / nj) + s % nj) as f64)
+            .collect();
+        let mut out = vec![];
+        write_vti(&mut out, &d, (ni, nj), 0.5, true).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.contains(r#"WholeExtent="0 2 0 1 0 0" Origin="0 0 0" Spacing="0.5 0.5 0.5""#));
+
+        let lines: Vec<&str> = text.lines().collect();
+        let start = lines
+            .iter()
+            .position(|l| l.contains(r#"Name="distance""#))
+            .unwrap()
+            + 1;
+        assert_eq!(lines[start..start + 6], ["0", "10", "20", "1", "11", "21"]);
+        assert_eq!(lines[start + 6].trim(), "</DataArray>");
+        // the gradient of 10 i + j with the spacing 0.5
+        let start = lines
+            .iter()
+            .position(|l| l.contains(r#"Name="gradient""#))
+            .unwrap()
+            + 1;
+        assert!(lines[start..start + 6].iter().all(|&l| l == "20 2 0"));
+    }
+}